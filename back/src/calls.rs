@@ -0,0 +1,142 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+};
+
+use tokio::sync::{
+    broadcast::{channel, Receiver, Sender},
+    watch,
+};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_util::sync::CancellationToken;
+
+use crate::{chat::relay_message, utils::tokens_match, CallDecision, Listener, RelayMessage};
+
+const CALL_RING_LENGTH: usize = 64;
+
+#[derive(Debug)]
+struct Caller {
+    id: u64,
+    name: String,
+    listener: Listener,
+    on_air: watch::Sender<bool>,
+    hang_up: CancellationToken,
+}
+
+#[derive(Debug)]
+pub struct CallIn {
+    // notices and the on air caller's frames, both headed to the streamer
+    call_sender: Sender<Message>,
+    callers: Mutex<Vec<Caller>>,
+    next_id: AtomicU64,
+    call_token: Option<String>,
+}
+
+impl CallIn {
+    pub fn new(call_token: Option<String>) -> Self {
+        CallIn {
+            call_sender: channel(CALL_RING_LENGTH).0,
+            callers: Mutex::new(vec![]),
+            next_id: AtomicU64::new(1),
+            call_token,
+        }
+    }
+
+    pub fn subscribe(&self) -> Receiver<Message> {
+        self.call_sender.subscribe()
+    }
+
+    pub fn is_authorized(&self, call_token: Option<&str>) -> bool {
+        self.call_token
+            .as_deref()
+            .is_some_and(|expected| tokens_match(call_token, expected))
+    }
+
+    pub fn join(
+        &self,
+        listener: Listener,
+        name: String,
+    ) -> (u64, watch::Receiver<bool>, CancellationToken) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (on_air, on_air_receiver) = watch::channel(false);
+        let hang_up = CancellationToken::new();
+        println!(
+            "Caller Waiting: {} | {}:{}",
+            name, listener.ip, listener.port
+        );
+        let _ = self
+            .call_sender
+            .send(relay_message(&RelayMessage::CallWaiting {
+                id,
+                name: name.clone(),
+            }));
+        self.callers.lock().unwrap().push(Caller {
+            id,
+            name,
+            listener,
+            on_air,
+            hang_up: hang_up.clone(),
+        });
+        (id, on_air_receiver, hang_up)
+    }
+
+    pub fn leave(&self, id: u64) {
+        let mut callers = self.callers.lock().unwrap();
+        if let Some(position) = callers.iter().position(|caller| caller.id == id) {
+            let caller = callers.remove(position);
+            println!(
+                "Caller Gone: {} | {}:{}",
+                caller.name, caller.listener.ip, caller.listener.port
+            );
+            let _ = self
+                .call_sender
+                .send(relay_message(&RelayMessage::CallEnded { id }));
+        }
+    }
+
+    // the streamer only ever hears one caller, taking a new one drops the previous off air
+    pub fn decide(&self, id: u64, decision: CallDecision) {
+        let callers = self.callers.lock().unwrap();
+        if !callers.iter().any(|caller| caller.id == id) {
+            eprintln!("Error: Call Decision | No Such Caller | {}", id);
+            return;
+        }
+        for caller in callers.iter() {
+            match decision {
+                CallDecision::Accept => {
+                    caller.on_air.send_if_modified(|on_air| {
+                        let was_on_air = *on_air;
+                        *on_air = caller.id == id;
+                        was_on_air != *on_air
+                    });
+                }
+                CallDecision::HangUp if caller.id == id => caller.hang_up.cancel(),
+                CallDecision::HangUp => {}
+            }
+        }
+    }
+
+    pub fn hang_up_all(&self) {
+        for caller in self.callers.lock().unwrap().iter() {
+            caller.hang_up.cancel();
+        }
+    }
+
+    pub fn waiting_messages(&self) -> Vec<Message> {
+        self.callers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|caller| {
+                relay_message(&RelayMessage::CallWaiting {
+                    id: caller.id,
+                    name: caller.name.clone(),
+                })
+            })
+            .collect()
+    }
+
+    pub fn forward(&self, frame: Message) {
+        let _ = self.call_sender.send(frame);
+    }
+}
@@ -0,0 +1,163 @@
+use std::{collections::HashSet, sync::Mutex, time::Duration};
+
+use tokio::{
+    sync::broadcast::{channel, Receiver, Sender},
+    time::Instant,
+};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::{utils::unix_timestamp, ListenerRequest, RelayMessage};
+
+const CHAT_RING_LENGTH: usize = 256;
+const CHAT_RATE_WINDOW: Duration = Duration::from_secs(60);
+const MAX_NICK_LENGTH: usize = 32;
+
+#[derive(Debug)]
+pub struct Chat {
+    chat_sender: Sender<Message>,
+    banned_words: Mutex<HashSet<String>>,
+    max_chat_length: usize,
+    max_chat_rate: u16,
+}
+
+#[derive(Debug)]
+pub struct ChatSession {
+    nick: Option<String>,
+    rate_window: (Instant, u16),
+}
+
+impl ChatSession {
+    pub fn new() -> Self {
+        ChatSession {
+            nick: None,
+            rate_window: (Instant::now(), 0),
+        }
+    }
+}
+
+impl Default for ChatSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Chat {
+    pub fn new(max_chat_length: usize, max_chat_rate: u16) -> Self {
+        Chat {
+            chat_sender: channel(CHAT_RING_LENGTH).0,
+            banned_words: Mutex::new(HashSet::new()),
+            max_chat_length,
+            max_chat_rate,
+        }
+    }
+
+    pub fn subscribe(&self) -> Receiver<Message> {
+        self.chat_sender.subscribe()
+    }
+
+    // relay notices ride the chat channel, every listener already follows it
+    pub fn announce(&self, message: Message) {
+        let _ = self.chat_sender.send(message);
+    }
+
+    pub fn banned_words(&self) -> Vec<String> {
+        let mut banned_words: Vec<String> =
+            self.banned_words.lock().unwrap().iter().cloned().collect();
+        banned_words.sort();
+        banned_words
+    }
+
+    pub fn ban_word(&self, word: &str) {
+        self.banned_words
+            .lock()
+            .unwrap()
+            .insert(word.to_lowercase());
+    }
+
+    pub fn unban_word(&self, word: &str) -> bool {
+        self.banned_words
+            .lock()
+            .unwrap()
+            .remove(&word.to_lowercase())
+    }
+
+    // answers the listener directly, chat lines themselves arrive through the subscription
+    pub fn handle(
+        &self,
+        chat_session: &mut ChatSession,
+        listener_request: ListenerRequest,
+    ) -> Option<Message> {
+        match listener_request {
+            ListenerRequest::Nick { nick } => {
+                let nick = nick.trim();
+                if !is_valid_nick(nick) {
+                    return Some(relay_error("Invalid Nick"));
+                }
+                if self.has_banned_word(nick) {
+                    return Some(relay_error("Banned Word"));
+                }
+                chat_session.nick = Some(nick.to_string());
+                Some(relay_message(&RelayMessage::Nick {
+                    nick: nick.to_string(),
+                }))
+            }
+            ListenerRequest::Chat { text } => {
+                let nick = match chat_session.nick.clone() {
+                    Some(nick) => nick,
+                    None => return Some(relay_error("No Nick")),
+                };
+                if chat_session.rate_window.0.elapsed() >= CHAT_RATE_WINDOW {
+                    chat_session.rate_window = (Instant::now(), 0);
+                }
+                chat_session.rate_window.1 = chat_session.rate_window.1.saturating_add(1);
+                if chat_session.rate_window.1 > self.max_chat_rate {
+                    return Some(relay_error("Rate Limited"));
+                }
+                let text = text.trim();
+                if text.is_empty() || text.chars().count() > self.max_chat_length {
+                    return Some(relay_error("Invalid Length"));
+                }
+                if self.has_banned_word(text) {
+                    return Some(relay_error("Banned Word"));
+                }
+                println!("Chat: {} -> {}", nick, text);
+                let _ = self.chat_sender.send(relay_message(&RelayMessage::Chat {
+                    nick,
+                    text: text.to_string(),
+                    timestamp: unix_timestamp(),
+                }));
+                None
+            }
+            ListenerRequest::SongRequest { .. }
+            | ListenerRequest::Library
+            | ListenerRequest::Quality(_) => None,
+        }
+    }
+
+    fn has_banned_word(&self, text: &str) -> bool {
+        let text = text.to_lowercase();
+        self.banned_words
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|banned_word| text.contains(banned_word.as_str()))
+    }
+}
+
+pub fn is_valid_nick(nick: &str) -> bool {
+    !nick.is_empty()
+        && nick.chars().count() <= MAX_NICK_LENGTH
+        && nick
+            .chars()
+            .all(|single| single.is_alphanumeric() || single == '_' || single == '-')
+}
+
+pub fn relay_message(relay_message: &RelayMessage) -> Message {
+    Message::text(serde_json::json!(relay_message).to_string())
+}
+
+pub fn relay_error(reason: &str) -> Message {
+    relay_message(&RelayMessage::Error {
+        reason: reason.to_string(),
+    })
+}
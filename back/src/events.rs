@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use axum::{
+    body::Bytes,
+    http::{header::CONTENT_TYPE, Request},
+};
+use http_body_util::Full;
+use hyper_util::{client::legacy::Client, rt::TokioExecutor};
+use serde::Serialize;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+use crate::ServerStatus;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RelayEvent {
+    pub mount: String,
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub kind: RelayEventKind,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RelayEventKind {
+    Status {
+        status: ServerStatus,
+        streamer_connected: bool,
+        listener_count: usize,
+        bottleneck: bool,
+        now_playing: Option<String>,
+    },
+    StreamerConnected {
+        ip: String,
+        port: u16,
+    },
+    StreamerDisconnected {
+        ip: String,
+        port: u16,
+    },
+    SilenceStarted,
+    SilenceEnded,
+    ListenersAbove {
+        threshold: usize,
+        listener_count: usize,
+    },
+    ListenersBelow {
+        threshold: usize,
+        listener_count: usize,
+    },
+    ListenerCount {
+        listener_count: usize,
+    },
+    Bottleneck {
+        bottleneck: bool,
+    },
+    NowPlaying {
+        title: String,
+    },
+}
+
+pub async fn webhook_notifier(webhook_urls: Vec<String>, mut event_receiver: Receiver<RelayEvent>) {
+    let client = Client::builder(TokioExecutor::new()).build_http::<Full<Bytes>>();
+    loop {
+        let relay_event = match event_receiver.recv().await {
+            Ok(relay_event) => relay_event,
+            Err(RecvError::Lagged(missed_event_count)) => {
+                eprintln!("Error: Webhook | Missed {} Event(s)", missed_event_count);
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        // every single join and leave is too chatty for a hook, thresholds cover it
+        if let RelayEventKind::ListenerCount { .. } = relay_event.kind {
+            continue;
+        }
+        let relay_event_json = match serde_json::to_string(&relay_event) {
+            Ok(relay_event_json) => relay_event_json,
+            Err(err_val) => {
+                eprintln!("Error: Webhook | Serialize | {}", err_val);
+                continue;
+            }
+        };
+        // one at a time, so a hook sees connected before disconnected
+        for webhook_url in &webhook_urls {
+            let request = match Request::post(webhook_url)
+                .header(CONTENT_TYPE, "application/json")
+                .body(Full::new(Bytes::from(relay_event_json.clone())))
+            {
+                Ok(request) => request,
+                Err(err_val) => {
+                    eprintln!("Error: Webhook | {} | {}", webhook_url, err_val);
+                    continue;
+                }
+            };
+            match tokio::time::timeout(WEBHOOK_TIMEOUT, client.request(request)).await {
+                Ok(Ok(response)) if response.status().is_success() => {}
+                Ok(Ok(response)) => {
+                    eprintln!("Error: Webhook | {} | {}", webhook_url, response.status())
+                }
+                Ok(Err(err_val)) => eprintln!("Error: Webhook | {} | {}", webhook_url, err_val),
+                Err(_) => eprintln!("Error: Webhook | {} | Timeout", webhook_url),
+            }
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use futures_util::{Sink, Stream, StreamExt};
+use tokio::sync::oneshot;
+use tokio_tungstenite::tungstenite::{Error, Message};
+use tokio_util::sync::CancellationToken;
+
+use crate::{schedule::Show, utils::unix_timestamp, HandoverPolicy, Streamer};
+
+const MAX_STANDBY_COUNT: usize = 4;
+
+pub trait StreamerSocket:
+    Stream<Item = Result<Message, Error>> + Sink<Message, Error = Error> + Unpin + Send
+{
+}
+
+impl<T> StreamerSocket for T where
+    T: Stream<Item = Result<Message, Error>> + Sink<Message, Error = Error> + Unpin + Send
+{
+}
+
+// plain and tls sockets wait in the same queue
+pub type BoxedStreamerSocket = Box<dyn StreamerSocket>;
+
+// who a streamer turned out to be, a mount without keys has nobody to tell apart
+#[derive(Debug, Clone, Default)]
+pub struct Authorization {
+    pub priority: u8,
+    pub show: Option<Show>,
+}
+
+pub struct Candidate {
+    pub streamer: Streamer,
+    pub priority: u8,
+    pub show: Option<Show>,
+    pub ws_stream: BoxedStreamerSocket,
+}
+
+impl Candidate {
+    // holding the key of a slot that has not begun yet
+    pub fn is_up_next(&self) -> bool {
+        self.show
+            .as_ref()
+            .is_some_and(|show| !show.is_on_air(unix_timestamp()))
+    }
+}
+
+struct Standby {
+    id: u64,
+    streamer: Streamer,
+    priority: u8,
+    show: Option<Show>,
+    promote: oneshot::Sender<oneshot::Sender<BoxedStreamerSocket>>,
+}
+
+pub struct Handover {
+    policy: HandoverPolicy,
+    on_air: Mutex<Option<(u8, CancellationToken)>>,
+    standbys: Arc<Mutex<Vec<Standby>>>,
+    next_id: AtomicU64,
+}
+
+impl Handover {
+    pub fn new(policy: HandoverPolicy) -> Self {
+        Handover {
+            policy,
+            on_air: Mutex::new(None),
+            standbys: Arc::new(Mutex::new(vec![])),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    pub fn policy(&self) -> HandoverPolicy {
+        self.policy
+    }
+
+    pub fn on_air(&self, priority: u8, streamer_kicker: CancellationToken) {
+        *self.on_air.lock().unwrap() = Some((priority, streamer_kicker));
+    }
+
+    pub fn off_air(&self) {
+        *self.on_air.lock().unwrap() = None;
+    }
+
+    // a streamer arriving at a busy mount, handed back whole with a reason when it is turned
+    // away so its socket can still be told why, a broadcast that ended meanwhile takes no one
+    #[allow(clippy::result_large_err)]
+    pub fn offer(
+        &self,
+        candidate: Candidate,
+        busy_canceller: &CancellationToken,
+    ) -> Result<(), (Candidate, &'static str)> {
+        if self.policy == HandoverPolicy::Reject && !candidate.is_up_next() {
+            return Err((candidate, "Mount Busy"));
+        }
+        let mut standbys = self.standbys.lock().unwrap();
+        // checked under the lock clear takes, so nothing slips in after it
+        if busy_canceller.is_cancelled() {
+            return Err((candidate, "Mount Off Air"));
+        }
+        if standbys.len() >= MAX_STANDBY_COUNT {
+            return Err((candidate, "Standby Full"));
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (promote, promoted) = oneshot::channel();
+        println!(
+            "Streamer Standby: {}:{} | Priority {}",
+            candidate.streamer.ip, candidate.streamer.port, candidate.priority
+        );
+        standbys.push(Standby {
+            id,
+            streamer: candidate.streamer.clone(),
+            priority: candidate.priority,
+            show: candidate.show.clone(),
+            promote,
+        });
+        if self.policy == HandoverPolicy::Priority {
+            if let Some((on_air_priority, streamer_kicker)) = self.on_air.lock().unwrap().as_ref() {
+                if candidate.priority > *on_air_priority {
+                    println!(
+                        "Streamer Takeover: {}:{} | Priority {} > {}",
+                        candidate.streamer.ip,
+                        candidate.streamer.port,
+                        candidate.priority,
+                        on_air_priority
+                    );
+                    streamer_kicker.cancel();
+                }
+            }
+        }
+        tokio::spawn(standby(
+            id,
+            candidate.streamer,
+            candidate.ws_stream,
+            promoted,
+            self.standbys.clone(),
+        ));
+        Ok(())
+    }
+
+    // the next streamer to go on air, highest priority first when priorities count
+    pub async fn next(&self) -> Option<Candidate> {
+        loop {
+            let standby = {
+                let mut standbys = self.standbys.lock().unwrap();
+                let position = match self.policy {
+                    HandoverPolicy::Priority => standbys
+                        .iter()
+                        .enumerate()
+                        .max_by_key(|(position, standby)| {
+                            (standby.priority, std::cmp::Reverse(*position))
+                        })
+                        .map(|(position, _)| position),
+                    _ => (!standbys.is_empty()).then_some(0),
+                }?;
+                standbys.remove(position)
+            };
+            let (handing_over, handed_over) = oneshot::channel();
+            if standby.promote.send(handing_over).is_err() {
+                continue;
+            }
+            // it may have hung up in between, the next one gets a chance then
+            if let Ok(ws_stream) = handed_over.await {
+                return Some(Candidate {
+                    streamer: standby.streamer,
+                    priority: standby.priority,
+                    show: standby.show,
+                    ws_stream,
+                });
+            }
+        }
+    }
+
+    pub fn clear(&self) {
+        self.standbys.lock().unwrap().clear();
+    }
+}
+
+async fn standby(
+    id: u64,
+    streamer: Streamer,
+    mut ws_stream: BoxedStreamerSocket,
+    mut promoted: oneshot::Receiver<oneshot::Sender<BoxedStreamerSocket>>,
+    standbys: Arc<Mutex<Vec<Standby>>>,
+) {
+    loop {
+        tokio::select! {
+            message = ws_stream.next() => match message {
+                // frames of a streamer still waiting are dropped, not buffered
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => {
+                    standbys.lock().unwrap().retain(|standby| standby.id != id);
+                    println!("Standby Left: {}:{}", streamer.ip, streamer.port);
+                    return;
+                }
+                Some(Ok(_)) => {}
+            },
+            handing_over = &mut promoted => {
+                if let Ok(handing_over) = handing_over {
+                    let _ = handing_over.send(ws_stream);
+                }
+                return;
+            }
+        }
+    }
+}
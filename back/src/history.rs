@@ -0,0 +1,114 @@
+use std::{
+    collections::VecDeque,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{statistics::MountStatistics, utils::unix_timestamp};
+
+const SAMPLE_PERIOD: Duration = Duration::from_secs(60);
+// a day of one minute samples
+const HISTORY_LENGTH: usize = 1440;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistorySample {
+    pub mount: String,
+    pub timestamp: u64,
+    pub listener_count: usize,
+    pub ingress_bitrate: u64,
+    pub egress_bitrate: u64,
+    pub bottleneck_events: u64,
+}
+
+#[derive(Debug)]
+pub struct History {
+    samples: Mutex<VecDeque<HistorySample>>,
+    history_log: Option<(String, Mutex<File>)>,
+}
+
+impl History {
+    pub fn open(history_log: Option<String>) -> Self {
+        let mut samples = VecDeque::with_capacity(HISTORY_LENGTH);
+        let history_log = history_log.and_then(|path| {
+            if let Ok(persisted_samples) = std::fs::read_to_string(&path) {
+                for persisted_sample in persisted_samples.lines() {
+                    if let Ok(persisted_sample) = serde_json::from_str(persisted_sample) {
+                        if samples.len() == HISTORY_LENGTH {
+                            samples.pop_front();
+                        }
+                        samples.push_back(persisted_sample);
+                    }
+                }
+            }
+            if let Some(parent) = Path::new(&path).parent() {
+                if let Err(err_val) = std::fs::create_dir_all(parent) {
+                    eprintln!("Error: History Log | {} | {}", path, err_val);
+                }
+            }
+            match OpenOptions::new().create(true).append(true).open(&path) {
+                Ok(file) => Some((path, Mutex::new(file))),
+                Err(err_val) => {
+                    eprintln!("Error: History Log | {} | {}", path, err_val);
+                    None
+                }
+            }
+        });
+        History {
+            samples: Mutex::new(samples),
+            history_log,
+        }
+    }
+
+    pub fn record(&self, history_sample: HistorySample) {
+        if let Some((path, file)) = &self.history_log {
+            let history_sample_json = serde_json::json!(history_sample);
+            if let Err(err_val) = writeln!(file.lock().unwrap(), "{}", history_sample_json) {
+                eprintln!("Error: History Log | {} | {}", path, err_val);
+            }
+        }
+        let mut samples = self.samples.lock().unwrap();
+        if samples.len() == HISTORY_LENGTH {
+            samples.pop_front();
+        }
+        samples.push_back(history_sample);
+    }
+
+    pub fn range(&self, from: Option<u64>, to: Option<u64>) -> Vec<HistorySample> {
+        self.samples
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|history_sample| {
+                from.is_none_or(|from| history_sample.timestamp >= from)
+                    && to.is_none_or(|to| history_sample.timestamp <= to)
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+pub async fn history_sampler(mount_statistics: Arc<MountStatistics>, history: Arc<History>) {
+    let mut sample_interval = tokio::time::interval(SAMPLE_PERIOD);
+    sample_interval.tick().await;
+    let mut previous_counters = mount_statistics.counters();
+    loop {
+        sample_interval.tick().await;
+        let counters = mount_statistics.counters();
+        history.record(HistorySample {
+            mount: mount_statistics.mount.clone(),
+            timestamp: unix_timestamp(),
+            listener_count: mount_statistics.listener_count(),
+            ingress_bitrate: (counters.bytes_in - previous_counters.bytes_in) * 8
+                / SAMPLE_PERIOD.as_secs(),
+            egress_bitrate: (counters.bytes_out - previous_counters.bytes_out) * 8
+                / SAMPLE_PERIOD.as_secs(),
+            bottleneck_events: counters.bottleneck_events - previous_counters.bottleneck_events,
+        });
+        previous_counters = counters;
+    }
+}
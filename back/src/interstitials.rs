@@ -0,0 +1,242 @@
+use std::{fs::File, path::Path, sync::Arc, time::Duration};
+
+use symphonia::core::{
+    audio::SampleBuffer,
+    codecs::{DecoderOptions, CODEC_TYPE_NULL},
+    errors::Error,
+    formats::FormatOptions,
+    io::MediaSourceStream,
+    meta::MetadataOptions,
+    probe::Hint,
+};
+
+use crate::{
+    mixing::Mixer,
+    schedule::{minute_of_week, parse_clock, MINUTES_PER_DAY, WEEKDAYS},
+    utils::unix_timestamp,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InterstitialMode {
+    // live feed stays on air underneath, just quieter
+    Duck,
+    // live feed is held silent until the clip is over
+    Pause,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Cue {
+    Hourly,
+    Daily(u64),
+    Weekly(u64),
+}
+
+#[derive(Debug, Clone)]
+pub struct Interstitial {
+    pub name: String,
+    cue: Cue,
+    pub mode: InterstitialMode,
+    // interleaved at the stream's rate and channel count, decoded once at startup
+    pub samples: Arc<Vec<f32>>,
+}
+
+impl Cue {
+    fn is_due(&self, minute_of_week: u64) -> bool {
+        match self {
+            Cue::Hourly => minute_of_week.is_multiple_of(60),
+            Cue::Daily(minute_of_day) => minute_of_week % MINUTES_PER_DAY == *minute_of_day,
+            Cue::Weekly(cue_minute) => minute_of_week == *cue_minute,
+        }
+    }
+}
+
+// "name | clip path | hourly, daily hh:mm or weekday hh:mm | duck or pause", one clip a line
+pub async fn load(
+    path: &str,
+    stream_sample_rate: u32,
+    stream_channels: usize,
+) -> Vec<Interstitial> {
+    let interstitials_unparsed = match tokio::fs::read_to_string(path).await {
+        Ok(interstitials_unparsed) => interstitials_unparsed,
+        Err(err_val) => {
+            eprintln!("Error: Interstitials | {} | {}", path, err_val);
+            return vec![];
+        }
+    };
+    let mut interstitials = vec![];
+    for line in interstitials_unparsed.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+        let [name, clip_path, cue, mode] = fields[..] else {
+            eprintln!("Error: Interstitials | Invalid Clip | {}", line);
+            continue;
+        };
+        let (Some(cue), Some(mode)) = (parse_cue(cue), parse_mode(mode)) else {
+            eprintln!("Error: Interstitials | Invalid Clip | {}", line);
+            continue;
+        };
+        if let Some(samples) = decode_clip(clip_path, stream_sample_rate, stream_channels) {
+            println!(
+                "Interstitial Loaded: {} | {:.1}s",
+                name,
+                samples.len() as f32 / (stream_sample_rate as usize * stream_channels) as f32
+            );
+            interstitials.push(Interstitial {
+                name: name.to_string(),
+                cue,
+                mode,
+                samples: Arc::new(samples),
+            });
+        }
+    }
+    interstitials
+}
+
+pub async fn interstitial_scheduler(interstitials: Vec<Interstitial>, mixer: Arc<Mixer>) {
+    if interstitials.is_empty() {
+        return;
+    }
+    loop {
+        // wakes just past every minute, so each cue fires once
+        tokio::time::sleep(Duration::from_secs(60 - unix_timestamp() % 60)).await;
+        let minute_of_week = minute_of_week(unix_timestamp());
+        for interstitial in interstitials
+            .iter()
+            .filter(|interstitial| interstitial.cue.is_due(minute_of_week))
+        {
+            mixer.cue_clip(
+                interstitial.name.clone(),
+                interstitial.samples.clone(),
+                interstitial.mode,
+            );
+        }
+    }
+}
+
+fn parse_cue(cue: &str) -> Option<Cue> {
+    match cue.split_once(' ') {
+        None if cue.eq_ignore_ascii_case("hourly") => Some(Cue::Hourly),
+        Some((day, clock)) if day.eq_ignore_ascii_case("daily") => {
+            Some(Cue::Daily(parse_clock(clock)?))
+        }
+        Some((weekday, clock)) => {
+            let weekday = WEEKDAYS
+                .iter()
+                .position(|known| known.eq_ignore_ascii_case(weekday))?
+                as u64;
+            Some(Cue::Weekly(weekday * MINUTES_PER_DAY + parse_clock(clock)?))
+        }
+        None => None,
+    }
+}
+
+fn parse_mode(mode: &str) -> Option<InterstitialMode> {
+    match mode.to_lowercase().as_str() {
+        "duck" => Some(InterstitialMode::Duck),
+        "pause" => Some(InterstitialMode::Pause),
+        _ => None,
+    }
+}
+
+fn decode_clip(path: &str, stream_sample_rate: u32, stream_channels: usize) -> Option<Vec<f32>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err_val) => {
+            eprintln!("Error: Interstitials | {} | {}", path, err_val);
+            return None;
+        }
+    };
+    let media_source_stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        hint.with_extension(extension);
+    }
+    let probed = match symphonia::default::get_probe().format(
+        &hint,
+        media_source_stream,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    ) {
+        Ok(probed) => probed,
+        Err(err_val) => {
+            eprintln!("Error: Interstitials | {} | {}", path, err_val);
+            return None;
+        }
+    };
+    let mut format = probed.format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)?;
+    let track_id = track.id;
+    let clip_sample_rate = track.codec_params.sample_rate?;
+    let mut decoder = match symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+    {
+        Ok(decoder) => decoder,
+        Err(err_val) => {
+            eprintln!("Error: Interstitials | {} | {}", path, err_val);
+            return None;
+        }
+    };
+    let mut clip_channels = 0;
+    let mut clip_samples = vec![];
+    while let Ok(packet) = format.next_packet() {
+        if packet.track_id() != track_id {
+            continue;
+        }
+        match decoder.decode(&packet) {
+            Ok(decoded) => {
+                let spec = *decoded.spec();
+                clip_channels = spec.channels.count();
+                let mut sample_buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+                sample_buffer.copy_interleaved_ref(decoded);
+                clip_samples.extend_from_slice(sample_buffer.samples());
+            }
+            Err(Error::DecodeError(_)) => continue,
+            Err(_) => break,
+        }
+    }
+    if clip_channels == 0 || clip_samples.len() < clip_channels {
+        eprintln!("Error: Interstitials | {} | No Audio", path);
+        return None;
+    }
+    let frames: Vec<Vec<f32>> = clip_samples
+        .chunks_exact(clip_channels)
+        .map(|frame| remap_channels(frame, stream_channels))
+        .collect();
+    Some(resample(&frames, clip_sample_rate, stream_sample_rate))
+}
+
+fn remap_channels(frame: &[f32], stream_channels: usize) -> Vec<f32> {
+    if stream_channels == 1 {
+        return vec![frame.iter().sum::<f32>() / frame.len() as f32];
+    }
+    (0..stream_channels)
+        .map(|channel| frame[channel.min(frame.len() - 1)])
+        .collect()
+}
+
+// linear is plenty for jingles and spoken ids
+fn resample(frames: &[Vec<f32>], clip_sample_rate: u32, stream_sample_rate: u32) -> Vec<f32> {
+    let step = clip_sample_rate as f64 / stream_sample_rate as f64;
+    let frame_count = (frames.len() as f64 / step) as usize;
+    let mut samples = Vec::with_capacity(frame_count * frames[0].len());
+    for stream_frame in 0..frame_count {
+        let position = stream_frame as f64 * step;
+        let index = position as usize;
+        let fraction = (position - index as f64) as f32;
+        let current = &frames[index];
+        let next = &frames[(index + 1).min(frames.len() - 1)];
+        for (current, next) in current.iter().zip(next) {
+            samples.push(current + (next - current) * fraction);
+        }
+    }
+    samples
+}
@@ -1,10 +1,37 @@
-use std::net::IpAddr;
+use std::{
+    collections::HashSet,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+};
 
+use calls::CallIn;
+use chat::Chat;
+use history::History;
+use interstitials::Interstitial;
+use mixing::Mixer;
+use requests::SongRequests;
+use schedule::Schedule;
 use serde::{Deserialize, Serialize};
+use statistics::{ListenerQuality, MountStatistics};
+use tokio_util::sync::CancellationToken;
+use webapp::WebApp;
 
+pub mod calls;
+pub mod chat;
+pub mod events;
+pub mod handover;
+pub mod history;
+pub mod interstitials;
+pub mod mixing;
+pub mod requests;
 pub mod routing;
+pub mod schedule;
+pub mod server;
+pub mod sessions;
+pub mod statistics;
 pub mod streaming;
 pub mod utils;
+pub mod webapp;
 
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -13,10 +40,85 @@ pub struct Config {
     pub streamer_address: String,
     pub latency: u16,
     pub tls: bool,
+    pub mount: String,
+    pub admin_token: Option<String>,
+    pub max_listeners: usize,
+    pub max_connections_per_ip: usize,
+    pub max_accept_rate: u16,
+    pub streamer_timeout: u64,
+    pub silence_timeout: u64,
+    pub max_frame_size: usize,
+    pub max_ingress_bitrate: usize,
+    pub listener_thresholds: Vec<usize>,
+    pub webhook_urls: Vec<String>,
+    pub session_log: String,
+    pub history_log: Option<String>,
+    pub max_chat_length: usize,
+    pub max_chat_rate: u16,
+    pub max_song_request_rate: u16,
+    pub call_token: Option<String>,
+    pub streamer_tokens: Vec<StreamerToken>,
+    pub handover_policy: HandoverPolicy,
+    pub schedule: Schedule,
+    pub stream_sample_rate: u32,
+    pub stream_channels: usize,
+    pub interstitials: Vec<Interstitial>,
+    pub front_dir: Option<String>,
+    pub public_address: Option<String>,
+}
+
+// what an embedded relay starts from, local and on ports the system picks
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            axum_address: "127.0.0.1:0".to_string(),
+            listener_address: "127.0.0.1:0".to_string(),
+            streamer_address: "127.0.0.1:0".to_string(),
+            latency: 50,
+            tls: false,
+            mount: "radioxide".to_string(),
+            admin_token: None,
+            max_listeners: 1000,
+            max_connections_per_ip: 4,
+            max_accept_rate: 50,
+            streamer_timeout: 10,
+            silence_timeout: 10,
+            max_frame_size: 1048576,
+            max_ingress_bitrate: 8000,
+            listener_thresholds: vec![10, 100, 1000],
+            webhook_urls: vec![],
+            session_log: "logs/listener_sessions.jsonl".to_string(),
+            history_log: None,
+            max_chat_length: 280,
+            max_chat_rate: 10,
+            max_song_request_rate: 5,
+            call_token: None,
+            streamer_tokens: vec![],
+            handover_policy: HandoverPolicy::Cohost,
+            schedule: Schedule::default(),
+            stream_sample_rate: 48000,
+            stream_channels: 2,
+            interstitials: vec![],
+            front_dir: None,
+            public_address: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
-pub struct AppState {}
+pub struct AppState {
+    pub mount_statistics: Arc<MountStatistics>,
+    pub history: Arc<History>,
+    pub chat: Arc<Chat>,
+    pub song_requests: Arc<SongRequests>,
+    pub call_in: Arc<CallIn>,
+    pub mixer: Arc<Mixer>,
+    pub schedule: Arc<Schedule>,
+    pub web_app: Option<WebApp>,
+    pub banned_ips: Arc<Mutex<HashSet<IpAddr>>>,
+    pub admin_token: Option<String>,
+    pub shutdown: CancellationToken,
+}
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Streamer {
@@ -24,13 +126,105 @@ pub struct Streamer {
     port: u16,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Listener {
     ip: IpAddr,
     port: u16,
 }
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-enum ServerStatus {
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ListenerRequest {
+    Nick { nick: String },
+    Chat { text: String },
+    SongRequest { song: String },
+    Library,
+    Quality(ListenerQuality),
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RelayMessage {
+    Nick {
+        nick: String,
+    },
+    Chat {
+        nick: String,
+        text: String,
+        timestamp: u64,
+    },
+    Error {
+        reason: String,
+    },
+    SongRequest {
+        id: u64,
+        song: String,
+        from_library: bool,
+    },
+    SongRequestStatus {
+        id: u64,
+        song: String,
+        status: SongRequestStatus,
+    },
+    Library {
+        songs: Vec<String>,
+    },
+    CallWaiting {
+        id: u64,
+        name: String,
+    },
+    CallEnded {
+        id: u64,
+    },
+    CallStatus {
+        id: u64,
+        on_air: bool,
+    },
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamerMessage {
+    Library {
+        songs: Vec<String>,
+    },
+    SongDecision {
+        id: u64,
+        decision: SongRequestStatus,
+    },
+    CallDecision {
+        id: u64,
+        decision: CallDecision,
+    },
+}
+// a key to the streamer port, the priority is what it counts for under the priority policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamerToken {
+    pub token: String,
+    pub priority: u8,
+}
+// what happens to a streamer arriving while another one is on air
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HandoverPolicy {
+    Cohost,
+    Reject,
+    Standby,
+    Priority,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallDecision {
+    Accept,
+    HangUp,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SongRequestStatus {
+    Pending,
+    Accepted,
+    Rejected,
+    Queued,
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ServerStatus {
     Alive,
     Unstable,
     Dead,
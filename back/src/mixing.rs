@@ -0,0 +1,279 @@
+use std::{
+    collections::VecDeque,
+    io::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use brotli::CompressorWriter;
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+
+use crate::{interstitials::InterstitialMode, Streamer};
+
+// a couple of seconds at the usual rates, a co-host running ahead is cut back to this
+const MAX_SOURCE_BUFFER_LENGTH: usize = 96000;
+const MAX_GAIN: f32 = 2.0;
+const DEFAULT_PRECISION: usize = 4;
+// widest sample the mixer reads, anything wider would not fit its digits
+pub const MAX_PRECISION: usize = 9;
+const DUCK_GAIN: f32 = 0.25;
+
+#[derive(Debug)]
+struct Source {
+    id: u64,
+    streamer: Streamer,
+    primary: bool,
+    gain: f32,
+    kicker: CancellationToken,
+    buffer: VecDeque<f32>,
+}
+
+#[derive(Debug)]
+struct Clip {
+    name: String,
+    samples: Arc<Vec<f32>>,
+    position: usize,
+    mode: InterstitialMode,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceReport {
+    pub id: u64,
+    pub ip: String,
+    pub port: u16,
+    pub primary: bool,
+    pub gain: f32,
+}
+
+#[derive(Debug)]
+pub struct Mixer {
+    sources: Mutex<Vec<Source>>,
+    clips: Mutex<VecDeque<Clip>>,
+    next_id: AtomicU64,
+}
+
+impl Default for Mixer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mixer {
+    pub fn new() -> Self {
+        Mixer {
+            sources: Mutex::new(vec![]),
+            clips: Mutex::new(VecDeque::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    pub fn add_source(&self, streamer: Streamer, primary: bool, kicker: CancellationToken) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.sources.lock().unwrap().push(Source {
+            id,
+            streamer,
+            primary,
+            gain: 1.0,
+            kicker,
+            buffer: VecDeque::new(),
+        });
+        id
+    }
+
+    pub fn remove_source(&self, id: u64) {
+        self.sources
+            .lock()
+            .unwrap()
+            .retain(|source| source.id != id);
+    }
+
+    // co-hosts ride on the primary's session, they go when it goes
+    pub fn kick_cohosts(&self) {
+        for source in self.sources.lock().unwrap().iter() {
+            if !source.primary {
+                source.kicker.cancel();
+            }
+        }
+    }
+
+    pub fn kick_source(&self, id: u64) -> bool {
+        match self
+            .sources
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|source| source.id == id)
+        {
+            Some(source) => {
+                source.kicker.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn set_gain(&self, id: u64, gain: f32) -> bool {
+        match self
+            .sources
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|source| source.id == id)
+        {
+            Some(source) => {
+                source.gain = gain.clamp(0.0, MAX_GAIN);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn report(&self) -> Vec<SourceReport> {
+        self.sources
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|source| SourceReport {
+                id: source.id,
+                ip: source.streamer.ip.to_string(),
+                port: source.streamer.port,
+                primary: source.primary,
+                gain: source.gain,
+            })
+            .collect()
+    }
+
+    // clips wait for their turn, nothing is cued while the mount is off air
+    pub fn cue_clip(&self, name: String, samples: Arc<Vec<f32>>, mode: InterstitialMode) {
+        if !self
+            .sources
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|source| source.primary)
+        {
+            println!("Interstitial Skipped: {} | Nobody On Air", name);
+            return;
+        }
+        println!("Interstitial Cued: {}", name);
+        self.clips.lock().unwrap().push_back(Clip {
+            name,
+            samples,
+            position: 0,
+            mode,
+        });
+    }
+
+    pub fn clear_clips(&self) {
+        self.clips.lock().unwrap().clear();
+    }
+
+    pub fn feed(&self, id: u64, uncompressed_data: &[u8]) {
+        let (samples, _) = decode_samples(uncompressed_data);
+        if let Some(source) = self
+            .sources
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|source| source.id == id)
+        {
+            source.buffer.extend(samples);
+            let overflow = source.buffer.len().saturating_sub(MAX_SOURCE_BUFFER_LENGTH);
+            source.buffer.drain(..overflow);
+        }
+    }
+
+    // the primary keeps the clock, every frame it sends pulls the same amount from each co-host
+    pub fn mix(&self, id: u64, uncompressed_data: &[u8]) -> Option<Vec<u8>> {
+        let mut sources = self.sources.lock().unwrap();
+        let mut clips = self.clips.lock().unwrap();
+        let primary_gain = sources
+            .iter()
+            .find(|source| source.id == id)
+            .map(|source| source.gain)
+            .unwrap_or(1.0);
+        if sources.len() < 2 && primary_gain == 1.0 && clips.is_empty() {
+            return None;
+        }
+        let (mut samples, precision) = decode_samples(uncompressed_data);
+        for sample in samples.iter_mut() {
+            *sample *= primary_gain;
+        }
+        for source in sources.iter_mut().filter(|source| source.id != id) {
+            let take = samples.len().min(source.buffer.len());
+            for (sample, cohost_sample) in samples.iter_mut().zip(source.buffer.drain(..take)) {
+                *sample += cohost_sample * source.gain;
+            }
+        }
+        if let Some(clip) = clips.front_mut() {
+            let live_gain = match clip.mode {
+                InterstitialMode::Duck => DUCK_GAIN,
+                InterstitialMode::Pause => 0.0,
+            };
+            let clip_end = (clip.position + samples.len()).min(clip.samples.len());
+            let clip_samples = &clip.samples[clip.position..clip_end];
+            for (index, sample) in samples.iter_mut().enumerate() {
+                *sample =
+                    *sample * live_gain + clip_samples.get(index).copied().unwrap_or_default();
+            }
+            clip.position = clip_end;
+            if clip.position >= clip.samples.len() {
+                println!("Interstitial Done: {}", clip.name);
+                clips.pop_front();
+            }
+        }
+        Some(encode_samples(&samples, precision))
+    }
+}
+
+fn decode_samples(uncompressed_data: &[u8]) -> (Vec<f32>, usize) {
+    let mut samples = vec![];
+    let mut precision = None;
+    let mut sample: Option<(f32, u64, usize)> = None;
+    for &single in uncompressed_data {
+        match single {
+            b'+' | b'-' => {
+                if let Some((sign, digits, length)) = sample.take() {
+                    precision.get_or_insert(length);
+                    samples.push(sign * digits as f32 / 10_f32.powi(length as i32));
+                }
+                sample = Some((if single == b'-' { -1.0 } else { 1.0 }, 0, 0));
+            }
+            digit => {
+                if let Some((_, digits, length)) = sample.as_mut() {
+                    *digits = digits
+                        .saturating_mul(10)
+                        .saturating_add(u64::from(digit - b'0'));
+                    *length += 1;
+                }
+            }
+        }
+    }
+    if let Some((sign, digits, length)) = sample {
+        precision.get_or_insert(length);
+        samples.push(sign * digits as f32 / 10_f32.powi(length as i32));
+    }
+    let precision = match precision {
+        Some(0) | None => DEFAULT_PRECISION,
+        Some(precision) => precision.min(MAX_PRECISION),
+    };
+    (samples, precision)
+}
+
+fn encode_samples(samples: &[f32], precision: usize) -> Vec<u8> {
+    let scale = 10_u64.pow(precision as u32);
+    let mut data = String::with_capacity(samples.len() * (precision + 1));
+    for &sample in samples {
+        data.push(if sample < 0.0 { '-' } else { '+' });
+        let magnitude = ((sample.abs() * scale as f32) as u64).min(scale - 1);
+        data.push_str(&format!("{:0width$}", magnitude, width = precision));
+    }
+    let mut compression_writer = CompressorWriter::new(vec![], 4096, 4, 24);
+    if let Err(err_val) = compression_writer.write_all(data.as_bytes()) {
+        eprintln!("Error: Mixer | Compression | {}", err_val);
+    }
+    compression_writer.into_inner()
+}
@@ -0,0 +1,176 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use serde::Serialize;
+use tokio::{
+    sync::broadcast::{channel, Receiver, Sender},
+    time::Instant,
+};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::{
+    chat::{relay_error, relay_message},
+    utils::unix_timestamp,
+    RelayMessage, SongRequestStatus,
+};
+
+const SONG_REQUEST_RING_LENGTH: usize = 64;
+const SONG_REQUEST_WINDOW: Duration = Duration::from_secs(3600);
+// decided requests stay listed until new ones push them out
+const MAX_SONG_REQUEST_COUNT: usize = 100;
+const MAX_SONG_LENGTH: usize = 256;
+const MAX_LIBRARY_LENGTH: usize = 10000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SongRequest {
+    pub id: u64,
+    pub song: String,
+    pub from_library: bool,
+    pub status: SongRequestStatus,
+    pub requested: u64,
+}
+
+#[derive(Debug)]
+pub struct SongRequests {
+    request_sender: Sender<Message>,
+    queue: Mutex<VecDeque<SongRequest>>,
+    library: Mutex<Vec<String>>,
+    rate_windows: Mutex<HashMap<IpAddr, (Instant, u16)>>,
+    next_id: AtomicU64,
+    max_song_request_rate: u16,
+}
+
+impl SongRequests {
+    pub fn new(max_song_request_rate: u16) -> Self {
+        SongRequests {
+            request_sender: channel(SONG_REQUEST_RING_LENGTH).0,
+            queue: Mutex::new(VecDeque::with_capacity(MAX_SONG_REQUEST_COUNT)),
+            library: Mutex::new(vec![]),
+            rate_windows: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+            max_song_request_rate,
+        }
+    }
+
+    pub fn subscribe(&self) -> Receiver<Message> {
+        self.request_sender.subscribe()
+    }
+
+    pub fn queue(&self) -> Vec<SongRequest> {
+        self.queue.lock().unwrap().iter().cloned().collect()
+    }
+
+    // a streamer connecting mid show still gets what piled up before it
+    pub fn pending_messages(&self) -> Vec<Message> {
+        self.queue
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|song_request| song_request.status == SongRequestStatus::Pending)
+            .map(|song_request| {
+                relay_message(&RelayMessage::SongRequest {
+                    id: song_request.id,
+                    song: song_request.song.clone(),
+                    from_library: song_request.from_library,
+                })
+            })
+            .collect()
+    }
+
+    pub fn library_message(&self) -> Message {
+        relay_message(&RelayMessage::Library {
+            songs: self.library.lock().unwrap().clone(),
+        })
+    }
+
+    pub fn set_library(&self, mut songs: Vec<String>) {
+        songs.retain(|song| song.len() <= MAX_SONG_LENGTH);
+        songs.truncate(MAX_LIBRARY_LENGTH);
+        println!("Library Published: {} Song(s)", songs.len());
+        *self.library.lock().unwrap() = songs;
+    }
+
+    pub fn request(&self, ip: IpAddr, song: String) -> Message {
+        let song = song.trim();
+        if song.is_empty() || song.len() > MAX_SONG_LENGTH {
+            return relay_error("Invalid Length");
+        }
+        {
+            let mut rate_windows = self.rate_windows.lock().unwrap();
+            rate_windows.retain(|_, rate_window| rate_window.0.elapsed() < SONG_REQUEST_WINDOW);
+            let rate_window = rate_windows.entry(ip).or_insert((Instant::now(), 0));
+            rate_window.1 = rate_window.1.saturating_add(1);
+            if rate_window.1 > self.max_song_request_rate {
+                return relay_error("Rate Limited");
+            }
+        }
+        let mut queue = self.queue.lock().unwrap();
+        if queue.len() >= MAX_SONG_REQUEST_COUNT {
+            match queue
+                .iter()
+                .position(|song_request| song_request.status != SongRequestStatus::Pending)
+            {
+                Some(decided) => {
+                    queue.remove(decided);
+                }
+                None => return relay_error("Queue Full"),
+            }
+        }
+        let song_request = SongRequest {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            song: song.to_string(),
+            from_library: self
+                .library
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|known| known == song),
+            status: SongRequestStatus::Pending,
+            requested: unix_timestamp(),
+        };
+        println!("Song Request: {} -> {}", ip, song_request.song);
+        let _ = self
+            .request_sender
+            .send(relay_message(&RelayMessage::SongRequest {
+                id: song_request.id,
+                song: song_request.song.clone(),
+                from_library: song_request.from_library,
+            }));
+        let status_message = song_request_status(&song_request);
+        queue.push_back(song_request);
+        status_message
+    }
+
+    pub fn decide(&self, id: u64, decision: SongRequestStatus) -> Option<Message> {
+        if decision == SongRequestStatus::Pending {
+            return None;
+        }
+        let mut queue = self.queue.lock().unwrap();
+        match queue.iter_mut().find(|song_request| song_request.id == id) {
+            Some(song_request) => {
+                song_request.status = decision;
+                println!("Song Request {:?}: {}", decision, song_request.song);
+                Some(song_request_status(song_request))
+            }
+            None => {
+                eprintln!("Error: Song Decision | No Such Request | {}", id);
+                None
+            }
+        }
+    }
+}
+
+fn song_request_status(song_request: &SongRequest) -> Message {
+    relay_message(&RelayMessage::SongRequestStatus {
+        id: song_request.id,
+        song: song_request.song.clone(),
+        status: song_request.status,
+    })
+}
@@ -1,28 +1,92 @@
-use crate::{AppState, CoinStatus, ServerStatus};
+use std::net::IpAddr;
+
+use crate::{
+    sessions::{session_report, sessions_csv},
+    utils::{tokens_match, unix_timestamp},
+    webapp::WEB_APP_PATH,
+    AppState, CoinStatus, Listener, ServerStatus,
+};
 use axum::{
-    body::Body, extract::State, http::StatusCode, response::IntoResponse, routing::get, Json,
-    Router,
+    body::Body,
+    extract::{Path, Query, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{delete, get, put},
+    Json, Router,
 };
+use futures_util::{stream, Stream, StreamExt};
 use rand::prelude::*;
-use tokio::fs::File;
+use serde::Deserialize;
+use tokio::{fs::File, sync::broadcast::error::RecvError};
 use tokio_util::io::ReaderStream;
-use tower_http::cors::CorsLayer;
+use tower_http::{cors::CorsLayer, services::ServeDir};
 
 pub async fn routing(State(state): State<AppState>) -> Router {
-    Router::new()
+    let admin_routing = Router::new()
+        .route("/listeners", get(admin_listeners))
+        .route("/listeners/:ip/:port", delete(admin_kick_listener))
+        .route("/streamer", delete(admin_kick_streamer))
+        .route("/bans", get(admin_bans))
+        .route("/bans/:ip", put(admin_ban).delete(admin_unban))
+        .route("/chat/words", get(admin_banned_words))
+        .route(
+            "/chat/words/:word",
+            put(admin_ban_word).delete(admin_unban_word),
+        )
+        .route("/sources", get(admin_sources))
+        .route(
+            "/sources/:id",
+            put(admin_source_gain).delete(admin_kick_source),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            admin_authentication,
+        ));
+    let mut router = Router::new()
         .route("/", get(alive))
+        .route("/health", get(alive))
         .route("/coin", get(flip_coin))
         .route("/stream", get(stream))
+        .route("/api/stats", get(stats))
+        .route("/api/stats/:mount", get(mount_stats))
+        .route("/api/sessions", get(sessions))
+        .route("/api/sessions/csv", get(sessions_export))
+        .route("/api/history", get(history))
+        .route("/api/requests", get(song_requests))
+        .route("/api/schedule", get(schedule))
+        .route("/metrics", get(metrics))
+        .route("/events", get(events));
+    // without a token there is no admin api at all, rather than one guarded by a known default
+    if state.admin_token.is_some() {
+        router = router.nest("/admin", admin_routing);
+    }
+    if state.web_app.is_some() {
+        let web_app_routing = Router::new()
+            .route("/", get(web_app_index))
+            .route("/index.html", get(web_app_index))
+            .fallback(web_app_file);
+        router = router
+            .route(&format!("{}/", WEB_APP_PATH), get(web_app_index))
+            .nest(WEB_APP_PATH, web_app_routing);
+    }
+    router
         .layer(CorsLayer::permissive())
         .with_state(state.clone())
 }
 
-async fn alive() -> impl IntoResponse {
-    let alive_json = serde_json::json!({
-        "status":ServerStatus::Alive,
-    });
+async fn alive(State(state): State<AppState>) -> impl IntoResponse {
+    let health = state.mount_statistics.health();
+    let status_code = match health.status {
+        ServerStatus::Alive | ServerStatus::Unstable => StatusCode::OK,
+        ServerStatus::Dead => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    let alive_json = serde_json::json!(health);
     println!("{}", alive_json);
-    (StatusCode::OK, Json(alive_json))
+    (status_code, Json(alive_json))
 }
 
 async fn flip_coin() -> impl IntoResponse {
@@ -46,3 +110,315 @@ async fn stream() -> impl IntoResponse {
     let stream = ReaderStream::new(file);
     Body::from_stream(stream)
 }
+
+async fn stats(State(state): State<AppState>) -> impl IntoResponse {
+    let stats_json = serde_json::json!({
+        "mounts":[state.mount_statistics.report()],
+    });
+    (StatusCode::OK, Json(stats_json))
+}
+
+async fn mount_stats(
+    State(state): State<AppState>,
+    Path(mount): Path<String>,
+) -> impl IntoResponse {
+    if mount != state.mount_statistics.mount {
+        let error_json = serde_json::json!({
+            "error":format!("No Such Mount: {}", mount),
+        });
+        return (StatusCode::NOT_FOUND, Json(error_json));
+    }
+    let mount_json = serde_json::json!(state.mount_statistics.report());
+    (StatusCode::OK, Json(mount_json))
+}
+
+#[derive(Debug, Deserialize)]
+struct TimeRange {
+    from: Option<u64>,
+    to: Option<u64>,
+}
+
+async fn sessions(
+    State(state): State<AppState>,
+    Query(time_range): Query<TimeRange>,
+) -> impl IntoResponse {
+    let listener_sessions = state
+        .mount_statistics
+        .listener_sessions(time_range.from, time_range.to);
+    let sessions_json = serde_json::json!(session_report(&listener_sessions));
+    (StatusCode::OK, Json(sessions_json))
+}
+
+async fn sessions_export(
+    State(state): State<AppState>,
+    Query(time_range): Query<TimeRange>,
+) -> impl IntoResponse {
+    let listener_sessions = state
+        .mount_statistics
+        .listener_sessions(time_range.from, time_range.to);
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "text/csv"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"listener_sessions.csv\"",
+            ),
+        ],
+        sessions_csv(&listener_sessions),
+    )
+}
+
+async fn history(
+    State(state): State<AppState>,
+    Query(time_range): Query<TimeRange>,
+) -> impl IntoResponse {
+    let history_json = serde_json::json!({
+        "samples":state.history.range(time_range.from, time_range.to),
+    });
+    (StatusCode::OK, Json(history_json))
+}
+
+async fn song_requests(State(state): State<AppState>) -> impl IntoResponse {
+    let song_requests_json = serde_json::json!({
+        "requests":state.song_requests.queue(),
+    });
+    (StatusCode::OK, Json(song_requests_json))
+}
+
+async fn schedule(State(state): State<AppState>) -> impl IntoResponse {
+    let schedule_json = serde_json::json!({
+        "shows":state.schedule.slots(unix_timestamp()),
+    });
+    (StatusCode::OK, Json(schedule_json))
+}
+
+async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.mount_statistics.metrics(),
+    )
+}
+
+async fn events(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let status_event = state.mount_statistics.status_event();
+    let event_receiver = state.mount_statistics.subscribe();
+    let relay_events = stream::unfold(
+        (event_receiver, state.mount_statistics.clone()),
+        |(mut event_receiver, mount_statistics)| async move {
+            let relay_event = match event_receiver.recv().await {
+                Ok(relay_event) => relay_event,
+                // fell behind, a fresh snapshot says more than the events we missed
+                Err(RecvError::Lagged(_)) => mount_statistics.status_event(),
+                Err(RecvError::Closed) => return None,
+            };
+            Some((relay_event, (event_receiver, mount_statistics)))
+        },
+    );
+    let sse_events = stream::once(async { status_event })
+        .chain(relay_events)
+        .map(|relay_event| Event::default().json_data(relay_event))
+        .take_until(state.shutdown.clone().cancelled_owned());
+    Sse::new(sse_events).keep_alive(KeepAlive::default())
+}
+
+async fn admin_authentication(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    request: Request,
+    next: Next,
+) -> Response {
+    let is_authorized = match (
+        state.admin_token.as_deref(),
+        headers
+            .get(header::AUTHORIZATION)
+            .and_then(|authorization| authorization.to_str().ok())
+            .and_then(|authorization| authorization.strip_prefix("Bearer ")),
+    ) {
+        (Some(expected_token), admin_token) => tokens_match(admin_token, expected_token),
+        (None, _) => false,
+    };
+    if !is_authorized {
+        let error_json = serde_json::json!({
+            "error":"Unauthorized",
+        });
+        return (StatusCode::UNAUTHORIZED, Json(error_json)).into_response();
+    }
+    next.run(request).await
+}
+
+async fn admin_listeners(State(state): State<AppState>) -> impl IntoResponse {
+    let listeners_json = serde_json::json!(state.mount_statistics.admin_listeners());
+    (StatusCode::OK, Json(listeners_json))
+}
+
+async fn admin_kick_listener(
+    State(state): State<AppState>,
+    Path((ip, port)): Path<(IpAddr, u16)>,
+) -> impl IntoResponse {
+    if state.mount_statistics.kick_listener(&Listener { ip, port }) {
+        println!("Admin: Listener Kicked | {}:{}", ip, port);
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+async fn admin_kick_streamer(State(state): State<AppState>) -> impl IntoResponse {
+    if state.mount_statistics.kick_streamer() {
+        println!("Admin: Streamer Kicked");
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+async fn admin_bans(State(state): State<AppState>) -> impl IntoResponse {
+    let bans_json = serde_json::json!(*state.banned_ips.lock().unwrap());
+    (StatusCode::OK, Json(bans_json))
+}
+
+async fn admin_ban(State(state): State<AppState>, Path(ip): Path<IpAddr>) -> impl IntoResponse {
+    state.banned_ips.lock().unwrap().insert(ip);
+    state.mount_statistics.kick_ip(ip);
+    println!("Admin: Banned | {}", ip);
+    StatusCode::NO_CONTENT
+}
+
+async fn admin_unban(State(state): State<AppState>, Path(ip): Path<IpAddr>) -> impl IntoResponse {
+    if state.banned_ips.lock().unwrap().remove(&ip) {
+        println!("Admin: Unbanned | {}", ip);
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+async fn admin_banned_words(State(state): State<AppState>) -> impl IntoResponse {
+    let banned_words_json = serde_json::json!(state.chat.banned_words());
+    (StatusCode::OK, Json(banned_words_json))
+}
+
+async fn admin_ban_word(
+    State(state): State<AppState>,
+    Path(word): Path<String>,
+) -> impl IntoResponse {
+    state.chat.ban_word(&word);
+    println!("Admin: Word Banned | {}", word);
+    StatusCode::NO_CONTENT
+}
+
+async fn admin_unban_word(
+    State(state): State<AppState>,
+    Path(word): Path<String>,
+) -> impl IntoResponse {
+    if state.chat.unban_word(&word) {
+        println!("Admin: Word Unbanned | {}", word);
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SourceGain {
+    gain: f32,
+}
+
+async fn admin_sources(State(state): State<AppState>) -> impl IntoResponse {
+    let sources_json = serde_json::json!(state.mixer.report());
+    (StatusCode::OK, Json(sources_json))
+}
+
+async fn admin_source_gain(
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+    Json(source_gain): Json<SourceGain>,
+) -> impl IntoResponse {
+    if state.mixer.set_gain(id, source_gain.gain) {
+        println!("Admin: Source Gain | {} -> {}", id, source_gain.gain);
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+async fn admin_kick_source(
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+) -> impl IntoResponse {
+    if state.mixer.kick_source(id) {
+        println!("Admin: Source Kicked | {}", id);
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+async fn web_app_index(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let web_app = match state.web_app.as_ref() {
+        Some(web_app) => web_app,
+        None => return StatusCode::NOT_FOUND.into_response(),
+    };
+    let host = headers
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok());
+    match web_app.index(host).await {
+        Ok(index) => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, "text/html; charset=utf-8"),
+                // the bundle names are not hashed, the page has to be checked every time
+                (header::CACHE_CONTROL, "no-cache"),
+            ],
+            index,
+        )
+            .into_response(),
+        Err(err_val) => {
+            eprintln!("Error: Web App | {} | {}", web_app.dir, err_val);
+            StatusCode::NOT_FOUND.into_response()
+        }
+    }
+}
+
+async fn web_app_file(State(state): State<AppState>, request: Request) -> Response {
+    let web_app = match state.web_app.as_ref() {
+        Some(web_app) => web_app,
+        None => return StatusCode::NOT_FOUND.into_response(),
+    };
+    let path = request.uri().path().to_string();
+    let headers = request.headers().clone();
+    let response = match ServeDir::new(&web_app.dir)
+        .append_index_html_on_directories(false)
+        .try_call(request)
+        .await
+    {
+        Ok(response) => response,
+        Err(err_val) => {
+            eprintln!("Error: Web App | {} | {}", path, err_val);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    // client side routes have no file behind them, the app answers those itself
+    let file_name = path.rsplit('/').next().unwrap_or_default();
+    if response.status() == StatusCode::NOT_FOUND && !file_name.contains('.') {
+        return web_app_index(State(state), headers).await;
+    }
+    let mut response = response.map(Body::new);
+    if response.status().is_success() {
+        if path.ends_with(".wasm") {
+            response.headers_mut().insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/wasm"),
+            );
+        }
+        response.headers_mut().insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("public, max-age=3600"),
+        );
+    }
+    response
+}
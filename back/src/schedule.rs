@@ -0,0 +1,180 @@
+use serde::Serialize;
+
+use crate::utils::tokens_match;
+
+pub const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+pub const MINUTES_PER_DAY: u64 = 1440;
+const MINUTES_PER_WEEK: u64 = MINUTES_PER_DAY * 7;
+
+// one weekly slot, times are utc and a slot ending before it starts runs past midnight
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Show {
+    pub name: String,
+    pub mount: String,
+    weekday: u64,
+    start: u64,
+    end: u64,
+    stream_key: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShowSlot {
+    pub name: String,
+    pub mount: String,
+    pub weekday: &'static str,
+    pub start: String,
+    pub end: String,
+    pub on_air: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Schedule {
+    shows: Vec<Show>,
+}
+
+impl Show {
+    pub fn is_allowed(&self, stream_key: Option<&str>) -> bool {
+        tokens_match(stream_key, &self.stream_key)
+    }
+
+    pub fn is_on_air(&self, timestamp: u64) -> bool {
+        self.elapsed(timestamp) < self.length()
+    }
+
+    // seconds left in the slot, nothing once it is over
+    pub fn remaining(&self, timestamp: u64) -> u64 {
+        (self.length() * 60).saturating_sub(self.elapsed(timestamp) * 60 + timestamp % 60)
+    }
+
+    // seconds until the slot next begins, nothing while it is on air
+    pub fn starts_in(&self, timestamp: u64) -> u64 {
+        if self.is_on_air(timestamp) {
+            return 0;
+        }
+        let slot_start = (self.weekday * MINUTES_PER_DAY + self.start) * 60;
+        let second_of_week = minute_of_week(timestamp) * 60 + timestamp % 60;
+        (slot_start + MINUTES_PER_WEEK * 60 - second_of_week) % (MINUTES_PER_WEEK * 60)
+    }
+
+    fn length(&self) -> u64 {
+        match (self.end + MINUTES_PER_DAY - self.start) % MINUTES_PER_DAY {
+            0 => MINUTES_PER_DAY,
+            length => length,
+        }
+    }
+
+    fn elapsed(&self, timestamp: u64) -> u64 {
+        let slot_start = self.weekday * MINUTES_PER_DAY + self.start;
+        (minute_of_week(timestamp) + MINUTES_PER_WEEK - slot_start) % MINUTES_PER_WEEK
+    }
+
+    fn slot(&self, timestamp: u64) -> ShowSlot {
+        ShowSlot {
+            name: self.name.clone(),
+            mount: self.mount.clone(),
+            weekday: WEEKDAYS[self.weekday as usize],
+            start: clock(self.start),
+            end: clock(self.end),
+            on_air: self.is_on_air(timestamp),
+        }
+    }
+}
+
+impl Schedule {
+    pub async fn load(path: &str) -> Self {
+        match tokio::fs::read_to_string(path).await {
+            Ok(schedule_unparsed) => Schedule::parse(&schedule_unparsed),
+            Err(err_val) => {
+                eprintln!("Error: Schedule | {} | {}", path, err_val);
+                Schedule::default()
+            }
+        }
+    }
+
+    // "name | mount | weekday | hh:mm-hh:mm | stream key", one show a line
+    pub fn parse(schedule_unparsed: &str) -> Self {
+        let mut shows = vec![];
+        for line in schedule_unparsed.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match parse_show(line) {
+                Some(show) => shows.push(show),
+                None => eprintln!("Error: Schedule | Invalid Show | {}", line),
+            }
+        }
+        Schedule { shows }
+    }
+
+    pub fn has_mount(&self, mount: &str) -> bool {
+        self.shows.iter().any(|show| show.mount == mount)
+    }
+
+    pub fn on_air(&self, mount: &str, timestamp: u64) -> Option<&Show> {
+        self.shows
+            .iter()
+            .find(|show| show.mount == mount && show.is_on_air(timestamp))
+    }
+
+    // the slot coming up on the mount if it begins within the window
+    pub fn up_next(&self, mount: &str, timestamp: u64, window: u64) -> Option<&Show> {
+        self.shows
+            .iter()
+            .filter(|show| show.mount == mount && !show.is_on_air(timestamp))
+            .min_by_key(|show| show.starts_in(timestamp))
+            .filter(|show| show.starts_in(timestamp) <= window)
+    }
+
+    pub fn slots(&self, timestamp: u64) -> Vec<ShowSlot> {
+        let mut slots: Vec<(u64, ShowSlot)> = self
+            .shows
+            .iter()
+            .map(|show| {
+                (
+                    show.weekday * MINUTES_PER_DAY + show.start,
+                    show.slot(timestamp),
+                )
+            })
+            .collect();
+        slots.sort_by_key(|(slot_start, _)| *slot_start);
+        slots.into_iter().map(|(_, slot)| slot).collect()
+    }
+}
+
+fn parse_show(line: &str) -> Option<Show> {
+    let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+    let [name, mount, weekday, window, stream_key] = fields[..] else {
+        return None;
+    };
+    let (start, end) = window.split_once('-')?;
+    if name.is_empty() || mount.is_empty() || stream_key.is_empty() {
+        return None;
+    }
+    Some(Show {
+        name: name.to_string(),
+        mount: mount.to_string(),
+        weekday: WEEKDAYS
+            .iter()
+            .position(|known| known.eq_ignore_ascii_case(weekday))? as u64,
+        start: parse_clock(start)?,
+        end: parse_clock(end)?,
+        stream_key: stream_key.to_string(),
+    })
+}
+
+pub fn parse_clock(clock: &str) -> Option<u64> {
+    let (hours, minutes) = clock.trim().split_once(':')?;
+    let (hours, minutes): (u64, u64) = (hours.parse().ok()?, minutes.parse().ok()?);
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+fn clock(minute_of_day: u64) -> String {
+    format!("{:02}:{:02}", minute_of_day / 60, minute_of_day % 60)
+}
+
+pub fn minute_of_week(timestamp: u64) -> u64 {
+    // the epoch fell on a thursday
+    let days = timestamp / 86400;
+    ((days + 3) % 7) * MINUTES_PER_DAY + (timestamp % 86400) / 60
+}
@@ -0,0 +1,279 @@
+use std::{
+    collections::HashSet,
+    io,
+    net::{SocketAddr, TcpListener},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum_server::{tls_rustls::RustlsConfig, Handle};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    calls::CallIn,
+    chat::Chat,
+    events,
+    history::{history_sampler, History},
+    interstitials::interstitial_scheduler,
+    mixing::Mixer,
+    requests::SongRequests,
+    routing,
+    sessions::SessionLog,
+    statistics::{AdminListenerReport, HealthReport, MountReport, MountStatistics},
+    streaming,
+    webapp::WebApp,
+    AppState, Config,
+};
+
+const GRACEFUL_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct RelayServer;
+
+#[derive(Debug, Clone, Default)]
+pub struct RelayServerBuilder {
+    config: Config,
+    shutdown: Option<CancellationToken>,
+}
+
+// a running relay, every address is the one actually bound so port 0 can be asked for
+#[derive(Debug)]
+pub struct RelayHandle {
+    state: AppState,
+    http_address: SocketAddr,
+    listener_address: SocketAddr,
+    streamer_address: SocketAddr,
+    server_task: JoinHandle<()>,
+    streaming_task: JoinHandle<()>,
+    background_tasks: Vec<JoinHandle<()>>,
+}
+
+impl RelayServer {
+    pub fn builder() -> RelayServerBuilder {
+        RelayServerBuilder::default()
+    }
+}
+
+impl RelayServerBuilder {
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn http_address(mut self, http_address: impl Into<String>) -> Self {
+        self.config.axum_address = http_address.into();
+        self
+    }
+
+    pub fn listener_address(mut self, listener_address: impl Into<String>) -> Self {
+        self.config.listener_address = listener_address.into();
+        self
+    }
+
+    pub fn streamer_address(mut self, streamer_address: impl Into<String>) -> Self {
+        self.config.streamer_address = streamer_address.into();
+        self
+    }
+
+    pub fn mount(mut self, mount: impl Into<String>) -> Self {
+        self.config.mount = mount.into();
+        self
+    }
+
+    pub fn admin_token(mut self, admin_token: impl Into<String>) -> Self {
+        self.config.admin_token = Some(admin_token.into());
+        self
+    }
+
+    pub fn session_log(mut self, session_log: impl Into<String>) -> Self {
+        self.config.session_log = session_log.into();
+        self
+    }
+
+    // the relay stops when this is cancelled, on top of RelayHandle::shutdown
+    pub fn shutdown(mut self, shutdown: CancellationToken) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
+    pub async fn start(self) -> io::Result<RelayHandle> {
+        let mut relay_config = self.config;
+        let shutdown = self.shutdown.unwrap_or_default();
+
+        // everything is bound up front, a taken port is an error here and not a panic later
+        let http_socket = bind(&relay_config.axum_address)?;
+        let http_address = http_socket.local_addr()?;
+        relay_config.axum_address = http_address.to_string();
+        let listener_socket =
+            tokio::net::TcpListener::from_std(bind(&relay_config.listener_address)?)?;
+        let listener_address = listener_socket.local_addr()?;
+        relay_config.listener_address = listener_address.to_string();
+        let streamer_socket =
+            tokio::net::TcpListener::from_std(bind(&relay_config.streamer_address)?)?;
+        let streamer_address = streamer_socket.local_addr()?;
+        relay_config.streamer_address = streamer_address.to_string();
+
+        let state = AppState {
+            mount_statistics: Arc::new(MountStatistics::new(
+                relay_config.mount.clone(),
+                SessionLog::open(relay_config.session_log.clone()),
+            )),
+            history: Arc::new(History::open(relay_config.history_log.clone())),
+            chat: Arc::new(Chat::new(
+                relay_config.max_chat_length,
+                relay_config.max_chat_rate,
+            )),
+            song_requests: Arc::new(SongRequests::new(relay_config.max_song_request_rate)),
+            call_in: Arc::new(CallIn::new(relay_config.call_token.clone())),
+            mixer: Arc::new(Mixer::new()),
+            schedule: Arc::new(relay_config.schedule.clone()),
+            web_app: WebApp::new(&relay_config),
+            banned_ips: Arc::new(Mutex::new(HashSet::new())),
+            admin_token: relay_config.admin_token.clone(),
+            shutdown: shutdown.clone(),
+        };
+        let app = routing::routing(axum::extract::State(state.clone())).await;
+        let rustls_config = match relay_config.tls {
+            true => Some(
+                RustlsConfig::from_pem_file(
+                    "certificates/fullchain.pem",
+                    "certificates/privkey.pem",
+                )
+                .await?,
+            ),
+            false => None,
+        };
+
+        let mut background_tasks = vec![
+            tokio::spawn(history_sampler(
+                state.mount_statistics.clone(),
+                state.history.clone(),
+            )),
+            tokio::spawn(interstitial_scheduler(
+                relay_config.interstitials.clone(),
+                state.mixer.clone(),
+            )),
+        ];
+        if !relay_config.webhook_urls.is_empty() {
+            background_tasks.push(tokio::spawn(events::webhook_notifier(
+                relay_config.webhook_urls.clone(),
+                state.mount_statistics.subscribe(),
+            )));
+        }
+        let streaming_task = tokio::spawn(streaming::start(
+            relay_config.clone(),
+            state.clone(),
+            streamer_socket,
+            listener_socket,
+        ));
+        let server_task = match rustls_config {
+            Some(rustls_config) => {
+                let handle = Handle::new();
+                tokio::spawn({
+                    let handle = handle.clone();
+                    let shutdown = shutdown.clone();
+                    async move {
+                        shutdown.cancelled().await;
+                        handle.graceful_shutdown(Some(GRACEFUL_SHUTDOWN_TIMEOUT));
+                    }
+                });
+                println!("\n\n\tOn Air -> https://{}\n\n", http_address);
+                tokio::spawn(async move {
+                    if let Err(err_val) = axum_server::from_tcp_rustls(http_socket, rustls_config)
+                        .handle(handle)
+                        .serve(app.into_make_service())
+                        .await
+                    {
+                        eprintln!("Error: HTTP Server | {}", err_val);
+                    }
+                })
+            }
+            None => {
+                let http_socket = tokio::net::TcpListener::from_std(http_socket)?;
+                println!("\n\n\tOn Air -> http://{}\n\n", http_address);
+                let shutdown = shutdown.clone();
+                tokio::spawn(async move {
+                    if let Err(err_val) = axum::serve(http_socket, app)
+                        .with_graceful_shutdown(shutdown.cancelled_owned())
+                        .await
+                    {
+                        eprintln!("Error: HTTP Server | {}", err_val);
+                    }
+                })
+            }
+        };
+        Ok(RelayHandle {
+            state,
+            http_address,
+            listener_address,
+            streamer_address,
+            server_task,
+            streaming_task,
+            background_tasks,
+        })
+    }
+}
+
+impl RelayHandle {
+    pub fn http_address(&self) -> SocketAddr {
+        self.http_address
+    }
+
+    pub fn listener_address(&self) -> SocketAddr {
+        self.listener_address
+    }
+
+    pub fn streamer_address(&self) -> SocketAddr {
+        self.streamer_address
+    }
+
+    pub fn state(&self) -> &AppState {
+        &self.state
+    }
+
+    pub fn shutdown_token(&self) -> CancellationToken {
+        self.state.shutdown.clone()
+    }
+
+    pub fn stats(&self) -> Vec<MountReport> {
+        vec![self.state.mount_statistics.report()]
+    }
+
+    pub fn health(&self) -> HealthReport {
+        self.state.mount_statistics.health()
+    }
+
+    pub fn mounts(&self) -> Vec<String> {
+        vec![self.state.mount_statistics.mount.clone()]
+    }
+
+    pub fn listeners(&self, mount: &str) -> Option<Vec<AdminListenerReport>> {
+        (mount == self.state.mount_statistics.mount)
+            .then(|| self.state.mount_statistics.admin_listeners())
+    }
+
+    pub async fn shutdown(self) {
+        self.state.shutdown.cancel();
+        self.stopped().await;
+    }
+
+    // resolves once the relay has stopped, for whoever cancelled the shutdown token elsewhere
+    pub async fn stopped(self) {
+        if let Err(err_val) = self.server_task.await {
+            eprintln!("Error: Shutdown | HTTP Server Task | {}", err_val);
+        }
+        if let Err(err_val) = self.streaming_task.await {
+            eprintln!("Error: Shutdown | Streaming Task | {}", err_val);
+        }
+        for background_task in self.background_tasks {
+            background_task.abort();
+        }
+        println!("Shutdown: Done");
+    }
+}
+
+fn bind(address: &str) -> io::Result<TcpListener> {
+    let socket = TcpListener::bind(address)?;
+    socket.set_nonblocking(true)?;
+    Ok(socket)
+}
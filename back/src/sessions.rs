@@ -0,0 +1,205 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::Write as _,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+
+// what the public reports cover, older sessions are only kept in the file
+const SESSION_HISTORY_LENGTH: usize = 10000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DisconnectReason {
+    Left,
+    Kicked,
+    SlowConsumer,
+    StreamerGone,
+    Shutdown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListenerSession {
+    pub mount: String,
+    pub show_started: Option<u64>,
+    #[serde(default)]
+    pub show: Option<String>,
+    pub joined: u64,
+    pub left: u64,
+    pub duration: u64,
+    pub bytes_sent: u64,
+    pub reason: DisconnectReason,
+    pub user_agent: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionReport {
+    pub session_count: usize,
+    pub listening_hours: f64,
+    pub peak_concurrent_listeners: usize,
+    pub shows: Vec<ShowReport>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShowReport {
+    pub show_started: u64,
+    pub show: Option<String>,
+    pub session_count: usize,
+    pub listening_hours: f64,
+    pub peak_concurrent_listeners: usize,
+}
+
+#[derive(Debug)]
+pub struct SessionLog {
+    path: String,
+    file: Mutex<Option<File>>,
+    sessions: Mutex<VecDeque<ListenerSession>>,
+}
+
+impl SessionLog {
+    pub fn open(path: String) -> Self {
+        let mut sessions = VecDeque::with_capacity(SESSION_HISTORY_LENGTH);
+        if let Ok(persisted_sessions) = File::open(&path) {
+            for persisted_session in BufReader::new(persisted_sessions).lines() {
+                let Ok(persisted_session) = persisted_session else {
+                    break;
+                };
+                if let Ok(persisted_session) = serde_json::from_str(&persisted_session) {
+                    if sessions.len() == SESSION_HISTORY_LENGTH {
+                        sessions.pop_front();
+                    }
+                    sessions.push_back(persisted_session);
+                }
+            }
+        }
+        if let Some(parent) = Path::new(&path).parent() {
+            if let Err(err_val) = std::fs::create_dir_all(parent) {
+                eprintln!("Error: Session Log | {} | {}", path, err_val);
+            }
+        }
+        let file = match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => Some(file),
+            Err(err_val) => {
+                eprintln!("Error: Session Log | {} | {}", path, err_val);
+                None
+            }
+        };
+        SessionLog {
+            path,
+            file: Mutex::new(file),
+            sessions: Mutex::new(sessions),
+        }
+    }
+
+    pub fn append(&self, listener_session: &ListenerSession) {
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            let listener_session_json = serde_json::json!(listener_session);
+            if let Err(err_val) = writeln!(file, "{}", listener_session_json) {
+                eprintln!("Error: Session Log | {} | {}", self.path, err_val);
+            }
+        }
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions.len() == SESSION_HISTORY_LENGTH {
+            sessions.pop_front();
+        }
+        sessions.push_back(listener_session.clone());
+    }
+
+    // sessions overlapping the range, from the ones kept in memory
+    pub fn range(&self, from: Option<u64>, to: Option<u64>) -> Vec<ListenerSession> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|listener_session| {
+                from.is_none_or(|from| listener_session.left >= from)
+                    && to.is_none_or(|to| listener_session.joined <= to)
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+pub fn session_report(listener_sessions: &[ListenerSession]) -> SessionReport {
+    let mut shows: BTreeMap<u64, Vec<&ListenerSession>> = BTreeMap::new();
+    for listener_session in listener_sessions {
+        if let Some(show_started) = listener_session.show_started {
+            shows
+                .entry(show_started)
+                .or_default()
+                .push(listener_session);
+        }
+    }
+    SessionReport {
+        session_count: listener_sessions.len(),
+        listening_hours: listening_hours(listener_sessions.iter()),
+        peak_concurrent_listeners: peak_concurrent_listeners(listener_sessions.iter()),
+        shows: shows
+            .into_iter()
+            .map(|(show_started, show_sessions)| ShowReport {
+                show_started,
+                show: show_sessions
+                    .iter()
+                    .find_map(|listener_session| listener_session.show.clone()),
+                session_count: show_sessions.len(),
+                listening_hours: listening_hours(show_sessions.iter().copied()),
+                peak_concurrent_listeners: peak_concurrent_listeners(show_sessions.iter().copied()),
+            })
+            .collect(),
+    }
+}
+
+pub fn sessions_csv(listener_sessions: &[ListenerSession]) -> String {
+    let mut sessions_csv =
+        String::from("mount,show_started,show,joined,left,duration,bytes_sent,reason,user_agent\n");
+    for listener_session in listener_sessions {
+        let _ = writeln!(
+            sessions_csv,
+            "{},{},{},{},{},{},{},{:?},{}",
+            csv_field(&listener_session.mount),
+            listener_session
+                .show_started
+                .map(|show_started| show_started.to_string())
+                .unwrap_or_default(),
+            csv_field(listener_session.show.as_deref().unwrap_or_default()),
+            listener_session.joined,
+            listener_session.left,
+            listener_session.duration,
+            listener_session.bytes_sent,
+            listener_session.reason,
+            csv_field(listener_session.user_agent.as_deref().unwrap_or_default()),
+        );
+    }
+    sessions_csv
+}
+
+fn csv_field(field: &str) -> String {
+    format!("\"{}\"", field.replace('"', "\"\""))
+}
+
+fn listening_hours<'a>(listener_sessions: impl Iterator<Item = &'a ListenerSession>) -> f64 {
+    listener_sessions
+        .map(|listener_session| listener_session.duration)
+        .sum::<u64>() as f64
+        / 3600.0
+}
+
+fn peak_concurrent_listeners<'a>(
+    listener_sessions: impl Iterator<Item = &'a ListenerSession>,
+) -> usize {
+    let mut changes: Vec<(u64, i64)> = listener_sessions
+        .flat_map(|listener_session| [(listener_session.joined, 1), (listener_session.left, -1)])
+        .collect();
+    // leaving and joining in the same second is a hand over, not an overlap
+    changes.sort();
+    let mut concurrent_listeners = 0_i64;
+    let mut peak = 0_i64;
+    for (_, change) in changes {
+        concurrent_listeners += change;
+        peak = peak.max(concurrent_listeners);
+    }
+    peak as usize
+}
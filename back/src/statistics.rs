@@ -0,0 +1,703 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{channel, Receiver, Sender};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    events::{RelayEvent, RelayEventKind},
+    sessions::{DisconnectReason, ListenerSession, SessionLog},
+    utils::unix_timestamp,
+    Listener, ServerStatus, Streamer,
+};
+
+const EVENT_BUS_LENGTH: usize = 256;
+// beacons come from the listener, anything past these is not a real player
+const MAX_QUALITY_COUNTER: u64 = u32::MAX as u64;
+const MAX_SAMPLE_RATE: u32 = 768000;
+
+#[derive(Debug)]
+pub struct MountStatistics {
+    pub mount: String,
+    streamer: Mutex<Option<(Streamer, u64, CancellationToken)>>,
+    listeners: Mutex<HashMap<Listener, Arc<ListenerStatistics>>>,
+    pending_listeners: Mutex<HashMap<IpAddr, usize>>,
+    total_egress: AtomicU64,
+    bottleneck: AtomicBool,
+    bottleneck_events: AtomicU64,
+    frames_in: AtomicU64,
+    bytes_in: AtomicU64,
+    frames_out: AtomicU64,
+    slow_consumer_disconnects: AtomicU64,
+    broadcast_lag: AtomicUsize,
+    tls_handshake_failures: AtomicU64,
+    listener_bound: AtomicBool,
+    pipeline_running: AtomicBool,
+    silence: AtomicBool,
+    rejected_frames: AtomicU64,
+    events: Sender<RelayEvent>,
+    now_playing: Mutex<Option<String>>,
+    show: Mutex<Option<String>>,
+    session_log: SessionLog,
+}
+
+// a listener place held from accept until the listener is counted or gives up
+#[derive(Debug)]
+pub struct ListenerSlot {
+    mount_statistics: Arc<MountStatistics>,
+    ip: IpAddr,
+}
+
+#[derive(Debug)]
+pub struct ListenerStatistics {
+    listener: Listener,
+    connected_since: u64,
+    show_started: Option<u64>,
+    show: Option<String>,
+    user_agent: Option<String>,
+    bytes_sent: AtomicU64,
+    queue_depth: AtomicUsize,
+    quality: Mutex<Option<ListenerQuality>>,
+    kicker: CancellationToken,
+}
+
+// what a listener's player says about itself, the counters run from when it connected
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ListenerQuality {
+    pub buffer_fill: f32,
+    pub underruns: u64,
+    pub dropped_packets: u64,
+    pub decode_errors: u64,
+    pub sample_rate: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MountReport {
+    pub mount: String,
+    pub streamer: Option<StreamerReport>,
+    pub listener_count: usize,
+    pub listeners: Vec<ListenerReport>,
+    pub total_egress: u64,
+    pub bottleneck: bool,
+    pub now_playing: Option<String>,
+    pub show: Option<String>,
+    pub quality: QualityReport,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QualityReport {
+    pub reporting_listeners: usize,
+    pub average_buffer_fill: f32,
+    pub underruns: u64,
+    pub dropped_packets: u64,
+    pub decode_errors: u64,
+    pub sample_rates: BTreeMap<u32, usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Counters {
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub bottleneck_events: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub status: ServerStatus,
+    pub streamer_connected: bool,
+    pub listener_bound: bool,
+    pub pipeline_running: bool,
+    pub bottleneck: bool,
+    pub silence: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamerReport {
+    pub ip: String,
+    pub port: u16,
+    pub connected_since: u64,
+}
+
+// public, so nothing in it says who the listener is
+#[derive(Debug, Clone, Serialize)]
+pub struct ListenerReport {
+    pub connected_since: u64,
+    pub bytes_sent: u64,
+    pub queue_depth: usize,
+    pub user_agent: Option<String>,
+    pub quality: Option<ListenerQuality>,
+}
+
+// the admin view, what is needed to kick or ban a listener
+#[derive(Debug, Clone, Serialize)]
+pub struct AdminListenerReport {
+    pub ip: String,
+    pub port: u16,
+    #[serde(flatten)]
+    pub listener: ListenerReport,
+}
+
+impl MountStatistics {
+    pub fn new(mount: String, session_log: SessionLog) -> Self {
+        MountStatistics {
+            mount,
+            streamer: Mutex::new(None),
+            listeners: Mutex::new(HashMap::new()),
+            pending_listeners: Mutex::new(HashMap::new()),
+            total_egress: AtomicU64::new(0),
+            bottleneck: AtomicBool::new(false),
+            bottleneck_events: AtomicU64::new(0),
+            frames_in: AtomicU64::new(0),
+            bytes_in: AtomicU64::new(0),
+            frames_out: AtomicU64::new(0),
+            slow_consumer_disconnects: AtomicU64::new(0),
+            broadcast_lag: AtomicUsize::new(0),
+            tls_handshake_failures: AtomicU64::new(0),
+            listener_bound: AtomicBool::new(false),
+            pipeline_running: AtomicBool::new(false),
+            silence: AtomicBool::new(false),
+            rejected_frames: AtomicU64::new(0),
+            events: channel(EVENT_BUS_LENGTH).0,
+            now_playing: Mutex::new(None),
+            show: Mutex::new(None),
+            session_log,
+        }
+    }
+
+    pub fn subscribe(&self) -> Receiver<RelayEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn publish(&self, kind: RelayEventKind) {
+        let _ = self.events.send(RelayEvent {
+            mount: self.mount.clone(),
+            timestamp: unix_timestamp(),
+            kind,
+        });
+    }
+
+    pub fn streamer_connected(&self, streamer: Streamer, streamer_kicker: CancellationToken) {
+        self.publish(RelayEventKind::StreamerConnected {
+            ip: streamer.ip.to_string(),
+            port: streamer.port,
+        });
+        *self.streamer.lock().unwrap() = Some((streamer, unix_timestamp(), streamer_kicker));
+    }
+
+    // listeners stay on through a handover, only the streamer entry changes hands
+    pub fn streamer_handed_over(&self, streamer: Streamer, streamer_kicker: CancellationToken) {
+        let previous_streamer = self.streamer.lock().unwrap().take();
+        if let Some((previous_streamer, _, _)) = previous_streamer {
+            self.publish(RelayEventKind::StreamerDisconnected {
+                ip: previous_streamer.ip.to_string(),
+                port: previous_streamer.port,
+            });
+        }
+        self.streamer_connected(streamer, streamer_kicker);
+    }
+
+    pub fn streamer_disconnected(&self) {
+        let streamer = self.streamer.lock().unwrap().take();
+        if let Some((streamer, _, _)) = streamer {
+            self.publish(RelayEventKind::StreamerDisconnected {
+                ip: streamer.ip.to_string(),
+                port: streamer.port,
+            });
+        }
+        let listeners: Vec<Arc<ListenerStatistics>> = self
+            .listeners
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(_, listener_statistics)| listener_statistics)
+            .collect();
+        for listener_statistics in &listeners {
+            self.session_log
+                .append(&listener_statistics.session(&self.mount, DisconnectReason::StreamerGone));
+        }
+        self.bottleneck.store(false, Ordering::Relaxed);
+        self.silence.store(false, Ordering::Relaxed);
+        *self.now_playing.lock().unwrap() = None;
+        *self.show.lock().unwrap() = None;
+        if !listeners.is_empty() {
+            self.publish(RelayEventKind::ListenerCount { listener_count: 0 });
+        }
+    }
+
+    // pending handshakes count against the limits too, a slow upgrade can not slip past them
+    pub fn reserve_listener(
+        self: &Arc<Self>,
+        ip: IpAddr,
+        max_listeners: usize,
+        max_connections_per_ip: usize,
+    ) -> Result<ListenerSlot, &'static str> {
+        let listeners = self.listeners.lock().unwrap();
+        let mut pending_listeners = self.pending_listeners.lock().unwrap();
+        let pending_from_ip = pending_listeners.get(&ip).copied().unwrap_or(0);
+        if listeners.len() + pending_listeners.values().sum::<usize>() >= max_listeners {
+            Err("Listener Limit Reached")
+        } else if listeners
+            .keys()
+            .filter(|listener| listener.ip == ip)
+            .count()
+            + pending_from_ip
+            >= max_connections_per_ip
+        {
+            Err("Connection Limit per IP Reached")
+        } else {
+            pending_listeners.insert(ip, pending_from_ip + 1);
+            Ok(ListenerSlot {
+                mount_statistics: self.clone(),
+                ip,
+            })
+        }
+    }
+
+    pub fn listener_connected(
+        &self,
+        listener_slot: ListenerSlot,
+        listener: Listener,
+        user_agent: Option<String>,
+    ) -> Arc<ListenerStatistics> {
+        let show_started = self
+            .streamer
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|(_, connected_since, _)| *connected_since);
+        let listener_statistics = Arc::new(ListenerStatistics {
+            listener: listener.clone(),
+            connected_since: unix_timestamp(),
+            show_started,
+            show: self.show.lock().unwrap().clone(),
+            user_agent,
+            bytes_sent: AtomicU64::new(0),
+            queue_depth: AtomicUsize::new(0),
+            quality: Mutex::new(None),
+            kicker: CancellationToken::new(),
+        });
+        let listener_count = {
+            let mut listeners = self.listeners.lock().unwrap();
+            listeners.insert(listener, listener_statistics.clone());
+            // released under the same lock, so the place is never counted twice or not at all
+            drop(listener_slot);
+            listeners.len()
+        };
+        self.publish(RelayEventKind::ListenerCount { listener_count });
+        listener_statistics
+    }
+
+    pub fn listener_disconnected(&self, listener: &Listener, reason: DisconnectReason) {
+        let (listener_statistics, listener_count) = {
+            let mut listeners = self.listeners.lock().unwrap();
+            match listeners.remove(listener) {
+                Some(listener_statistics) => (listener_statistics, listeners.len()),
+                None => return,
+            }
+        };
+        self.session_log
+            .append(&listener_statistics.session(&self.mount, reason));
+        self.publish(RelayEventKind::ListenerCount { listener_count });
+    }
+
+    pub fn kick_listener(&self, listener: &Listener) -> bool {
+        match self.listeners.lock().unwrap().get(listener) {
+            Some(listener_statistics) => {
+                listener_statistics.kicker.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn kick_streamer(&self) -> bool {
+        match self.streamer.lock().unwrap().as_ref() {
+            Some((_, _, streamer_kicker)) => {
+                streamer_kicker.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn kick_ip(&self, ip: IpAddr) {
+        for (listener, listener_statistics) in self.listeners.lock().unwrap().iter() {
+            if listener.ip == ip {
+                listener_statistics.kicker.cancel();
+            }
+        }
+        if let Some((streamer, _, streamer_kicker)) = self.streamer.lock().unwrap().as_ref() {
+            if streamer.ip == ip {
+                streamer_kicker.cancel();
+            }
+        }
+    }
+
+    pub fn message_sent(&self, listener_statistics: &ListenerStatistics, bytes: usize) {
+        listener_statistics
+            .bytes_sent
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+        self.total_egress.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.frames_out.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn message_received(&self, bytes: usize) {
+        self.frames_in.fetch_add(1, Ordering::Relaxed);
+        self.bytes_in.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn slow_consumer_disconnected(&self) {
+        self.slow_consumer_disconnects
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn frame_rejected(&self) {
+        self.rejected_frames.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn tls_handshake_failed(&self) {
+        self.tls_handshake_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn set_broadcast_lag(&self, broadcast_lag: usize) {
+        self.broadcast_lag.store(broadcast_lag, Ordering::Relaxed);
+    }
+
+    pub fn set_bottleneck(&self, bottleneck: bool) {
+        if self.bottleneck.swap(bottleneck, Ordering::Relaxed) != bottleneck {
+            if bottleneck {
+                self.bottleneck_events.fetch_add(1, Ordering::Relaxed);
+            }
+            self.publish(RelayEventKind::Bottleneck { bottleneck });
+        }
+    }
+
+    pub fn set_now_playing(&self, title: String) {
+        *self.now_playing.lock().unwrap() = Some(title.clone());
+        self.publish(RelayEventKind::NowPlaying { title });
+    }
+
+    pub fn set_show(&self, show: Option<String>) {
+        *self.show.lock().unwrap() = show;
+    }
+
+    pub fn set_listener_bound(&self, listener_bound: bool) {
+        self.listener_bound.store(listener_bound, Ordering::Relaxed);
+    }
+
+    pub fn set_pipeline_running(&self, pipeline_running: bool) {
+        self.pipeline_running
+            .store(pipeline_running, Ordering::Relaxed);
+    }
+
+    pub fn set_silence(&self, silence: bool) {
+        if self.silence.swap(silence, Ordering::Relaxed) != silence {
+            self.publish(match silence {
+                true => RelayEventKind::SilenceStarted,
+                false => RelayEventKind::SilenceEnded,
+            });
+        }
+    }
+
+    pub fn is_silent(&self) -> bool {
+        self.silence.load(Ordering::Relaxed)
+    }
+
+    pub fn listener_sessions(&self, from: Option<u64>, to: Option<u64>) -> Vec<ListenerSession> {
+        self.session_log.range(from, to)
+    }
+
+    pub fn listener_count(&self) -> usize {
+        self.listeners.lock().unwrap().len()
+    }
+
+    pub fn listener_count_from(&self, ip: IpAddr) -> usize {
+        self.listeners
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|listener| listener.ip == ip)
+            .count()
+    }
+
+    pub fn report(&self) -> MountReport {
+        let streamer =
+            self.streamer
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(|(streamer, connected_since, _)| StreamerReport {
+                    ip: streamer.ip.to_string(),
+                    port: streamer.port,
+                    connected_since: *connected_since,
+                });
+        let listeners: Vec<ListenerReport> = self
+            .listeners
+            .lock()
+            .unwrap()
+            .values()
+            .map(|listener_statistics| listener_statistics.report())
+            .collect();
+        let quality = quality_report(&listeners);
+        MountReport {
+            mount: self.mount.clone(),
+            streamer,
+            listener_count: listeners.len(),
+            listeners,
+            total_egress: self.total_egress.load(Ordering::Relaxed),
+            bottleneck: self.bottleneck.load(Ordering::Relaxed),
+            now_playing: self.now_playing.lock().unwrap().clone(),
+            show: self.show.lock().unwrap().clone(),
+            quality,
+        }
+    }
+
+    pub fn admin_listeners(&self) -> Vec<AdminListenerReport> {
+        self.listeners
+            .lock()
+            .unwrap()
+            .values()
+            .map(|listener_statistics| AdminListenerReport {
+                ip: listener_statistics.listener.ip.to_string(),
+                port: listener_statistics.listener.port,
+                listener: listener_statistics.report(),
+            })
+            .collect()
+    }
+
+    pub fn health(&self) -> HealthReport {
+        let streamer_connected = self.streamer.lock().unwrap().is_some();
+        let listener_bound = self.listener_bound.load(Ordering::Relaxed);
+        let pipeline_running = self.pipeline_running.load(Ordering::Relaxed);
+        let bottleneck = self.bottleneck.load(Ordering::Relaxed);
+        let silence = self.is_silent();
+        let status = if streamer_connected && !(listener_bound && pipeline_running) {
+            ServerStatus::Dead
+        } else if !streamer_connected || bottleneck || silence {
+            ServerStatus::Unstable
+        } else {
+            ServerStatus::Alive
+        };
+        HealthReport {
+            status,
+            streamer_connected,
+            listener_bound,
+            pipeline_running,
+            bottleneck,
+            silence,
+        }
+    }
+
+    pub fn counters(&self) -> Counters {
+        Counters {
+            bytes_in: self.bytes_in.load(Ordering::Relaxed),
+            bytes_out: self.total_egress.load(Ordering::Relaxed),
+            bottleneck_events: self.bottleneck_events.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn status_event(&self) -> RelayEvent {
+        let health = self.health();
+        RelayEvent {
+            mount: self.mount.clone(),
+            timestamp: unix_timestamp(),
+            kind: RelayEventKind::Status {
+                status: health.status,
+                streamer_connected: health.streamer_connected,
+                listener_count: self.listener_count(),
+                bottleneck: health.bottleneck,
+                now_playing: self.now_playing.lock().unwrap().clone(),
+            },
+        }
+    }
+
+    pub fn metrics(&self) -> String {
+        let streamer_connected_since = self
+            .streamer
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|(_, connected_since, _)| *connected_since);
+        let uptime = streamer_connected_since
+            .map(|connected_since| unix_timestamp().saturating_sub(connected_since))
+            .unwrap_or_default();
+        let metrics = [
+            (
+                "radioxide_listeners",
+                "gauge",
+                "Connected listeners.",
+                self.listener_count() as u64,
+            ),
+            (
+                "radioxide_streamer_connected",
+                "gauge",
+                "Whether a streamer is connected.",
+                streamer_connected_since.is_some() as u64,
+            ),
+            (
+                "radioxide_mount_uptime_seconds",
+                "gauge",
+                "Seconds since the current streamer connected.",
+                uptime,
+            ),
+            (
+                "radioxide_frames_in_total",
+                "counter",
+                "Frames received from streamers.",
+                self.frames_in.load(Ordering::Relaxed),
+            ),
+            (
+                "radioxide_bytes_in_total",
+                "counter",
+                "Bytes received from streamers.",
+                self.bytes_in.load(Ordering::Relaxed),
+            ),
+            (
+                "radioxide_frames_out_total",
+                "counter",
+                "Frames sent to listeners.",
+                self.frames_out.load(Ordering::Relaxed),
+            ),
+            (
+                "radioxide_bytes_out_total",
+                "counter",
+                "Bytes sent to listeners.",
+                self.total_egress.load(Ordering::Relaxed),
+            ),
+            (
+                "radioxide_slow_consumer_disconnects_total",
+                "counter",
+                "Listeners disconnected for falling behind.",
+                self.slow_consumer_disconnects.load(Ordering::Relaxed),
+            ),
+            (
+                "radioxide_broadcast_lag",
+                "gauge",
+                "Frames waiting in the broadcast channel.",
+                self.broadcast_lag.load(Ordering::Relaxed) as u64,
+            ),
+            (
+                "radioxide_tls_handshake_failures_total",
+                "counter",
+                "Failed TLS handshakes.",
+                self.tls_handshake_failures.load(Ordering::Relaxed),
+            ),
+            (
+                "radioxide_rejected_frames_total",
+                "counter",
+                "Streamer frames dropped by validation.",
+                self.rejected_frames.load(Ordering::Relaxed),
+            ),
+            (
+                "radioxide_silence",
+                "gauge",
+                "Whether the streamer has been sending digital silence.",
+                self.is_silent() as u64,
+            ),
+        ];
+        let mut metrics_text = String::new();
+        for (name, kind, help, value) in metrics {
+            let _ = writeln!(metrics_text, "# HELP {} {}", name, help);
+            let _ = writeln!(metrics_text, "# TYPE {} {}", name, kind);
+            let _ = writeln!(
+                metrics_text,
+                "{}{{mount=\"{}\"}} {}",
+                name, self.mount, value
+            );
+        }
+        metrics_text
+    }
+}
+
+impl Drop for ListenerSlot {
+    fn drop(&mut self) {
+        let mut pending_listeners = self.mount_statistics.pending_listeners.lock().unwrap();
+        if let Some(pending_from_ip) = pending_listeners.get_mut(&self.ip) {
+            *pending_from_ip -= 1;
+            if *pending_from_ip == 0 {
+                pending_listeners.remove(&self.ip);
+            }
+        }
+    }
+}
+
+impl ListenerStatistics {
+    pub fn kicked(&self) -> CancellationToken {
+        self.kicker.clone()
+    }
+
+    pub fn set_queue_depth(&self, queue_depth: usize) {
+        self.queue_depth.store(queue_depth, Ordering::Relaxed);
+    }
+
+    pub fn set_quality(&self, quality: ListenerQuality) {
+        let buffer_fill = match quality.buffer_fill.is_nan() {
+            true => 0.0,
+            false => quality.buffer_fill.clamp(0.0, 1.0),
+        };
+        *self.quality.lock().unwrap() = Some(ListenerQuality {
+            buffer_fill,
+            underruns: quality.underruns.min(MAX_QUALITY_COUNTER),
+            dropped_packets: quality.dropped_packets.min(MAX_QUALITY_COUNTER),
+            decode_errors: quality.decode_errors.min(MAX_QUALITY_COUNTER),
+            sample_rate: quality.sample_rate.min(MAX_SAMPLE_RATE),
+        });
+    }
+
+    fn session(&self, mount: &str, reason: DisconnectReason) -> ListenerSession {
+        let left = unix_timestamp();
+        ListenerSession {
+            mount: mount.to_string(),
+            show_started: self.show_started,
+            show: self.show.clone(),
+            joined: self.connected_since,
+            left,
+            duration: left.saturating_sub(self.connected_since),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            reason,
+            user_agent: self.user_agent.clone(),
+        }
+    }
+
+    fn report(&self) -> ListenerReport {
+        ListenerReport {
+            connected_since: self.connected_since,
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            queue_depth: self.queue_depth.load(Ordering::Relaxed),
+            user_agent: self.user_agent.clone(),
+            quality: *self.quality.lock().unwrap(),
+        }
+    }
+}
+
+// listeners that never sent a beacon are left out rather than counted as healthy
+fn quality_report(listeners: &[ListenerReport]) -> QualityReport {
+    let mut quality_report = QualityReport::default();
+    for quality in listeners.iter().filter_map(|listener| listener.quality) {
+        quality_report.reporting_listeners += 1;
+        quality_report.average_buffer_fill += quality.buffer_fill;
+        quality_report.underruns = quality_report.underruns.saturating_add(quality.underruns);
+        quality_report.dropped_packets = quality_report
+            .dropped_packets
+            .saturating_add(quality.dropped_packets);
+        quality_report.decode_errors = quality_report
+            .decode_errors
+            .saturating_add(quality.decode_errors);
+        *quality_report
+            .sample_rates
+            .entry(quality.sample_rate)
+            .or_default() += 1;
+    }
+    if quality_report.reporting_listeners > 0 {
+        quality_report.average_buffer_fill /= quality_report.reporting_listeners as f32;
+    }
+    quality_report
+}
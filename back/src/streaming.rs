@@ -1,16 +1,20 @@
 use std::{
+    collections::HashSet,
     fs::File,
-    io::{self, BufReader},
-    sync::Arc,
+    io::{self, BufReader, Read},
+    net::IpAddr,
+    sync::{Arc, Mutex},
     time::Duration,
 };
 
+use brotli::Decompressor;
 use futures_util::{SinkExt, StreamExt};
 use rustls_pemfile::{certs, pkcs8_private_keys};
 
 use tokio::{
-    net::TcpListener,
-    sync::broadcast::{channel, Receiver, Sender},
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite},
+    net::{TcpListener, TcpStream},
+    sync::broadcast::{channel, error::RecvError, Receiver, Sender},
     task::JoinHandle,
     time::Instant,
 };
@@ -18,33 +22,131 @@ use tokio_rustls::{
     rustls::pki_types::{CertificateDer, PrivateKeyDer},
     TlsAcceptor,
 };
-use tokio_tungstenite::tungstenite::{util::NonBlockingResult, Error, Message};
+use tokio_tungstenite::tungstenite::{
+    handshake::server::{ErrorResponse, Request, Response},
+    http::header::USER_AGENT,
+    protocol::{frame::coding::CloseCode, CloseFrame, WebSocketConfig},
+    Error, Message,
+};
+use tokio_util::sync::CancellationToken;
 
-use crate::{Config, Listener, Streamer};
+use crate::{
+    chat::{is_valid_nick, relay_error, relay_message, ChatSession},
+    events::RelayEventKind,
+    handover::{Authorization, BoxedStreamerSocket, Candidate, Handover},
+    mixing::MAX_PRECISION,
+    schedule::Show,
+    sessions::DisconnectReason,
+    statistics::{ListenerSlot, ListenerStatistics},
+    utils::{tokens_match, unix_timestamp},
+    AppState, Config, HandoverPolicy, Listener, ListenerRequest, RelayMessage, Streamer,
+    StreamerMessage,
+};
 
 const BUFFER_LENGTH: usize = 1000000;
+const FRAME_RING_LENGTH: usize = 64;
 const MAX_TOLERATED_MESSAGE_COUNT: usize = 10;
-pub async fn start(relay_configs: Config) {
-    let timer = Instant::now();
+const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+// accept keeps failing while the process is out of descriptors, this keeps it from spinning
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+const STREAMER_PING_INTERVAL: Duration = Duration::from_secs(5);
+const INGRESS_WINDOW: Duration = Duration::from_secs(1);
+const MAX_DECOMPRESSED_FRAME_SIZE: u64 = 1000000;
+const MAX_TITLE_LENGTH: usize = 256;
+const CALL_PATH: &str = "/call";
+// listeners only send chat, song requests and beacons, callers send a few seconds of audio at most
+const LISTENER_MAX_MESSAGE_SIZE: usize = 4096;
+const CALL_MAX_MESSAGE_SIZE: usize = 262144;
+// the host of the slot on air outranks any other key, the next slot's host outranks nobody
+const SHOW_PRIORITY: u8 = u8::MAX;
+const NEXT_SHOW_PRIORITY: u8 = 0;
+// how long before its slot the next host may connect and wait
+const NEXT_SLOT_WINDOW: u64 = 600;
+
+#[derive(Debug, Default)]
+struct Handshake {
+    user_agent: Option<String>,
+    path: String,
+    query: Option<String>,
+}
+
+// what every task of the relay shares, one clone per connection instead of an argument each
+#[derive(Clone)]
+struct Relay {
+    state: AppState,
+    relay_configs: Arc<Config>,
+    handover: Arc<Handover>,
+    timer: Instant,
+}
+
+// the listener side of one broadcast, torn down together once the streamer is gone
+struct Pipeline {
+    frame_producer: Sender<Message>,
+    listener_stream_tasks_receiver: tokio::sync::mpsc::Receiver<JoinHandle<()>>,
+    listener_handler_task: JoinHandle<()>,
+    pacer_task: JoinHandle<()>,
+    listener_canceller: CancellationToken,
+}
+
+// both sockets are bound by the caller and live as long as the relay, listeners are only
+// taken in while someone is on air
+pub async fn start(
+    relay_configs: Config,
+    state: AppState,
+    streamer_socket: TcpListener,
+    listener_socket: TcpListener,
+) {
+    let streamer_socket = Arc::new(streamer_socket);
+    let listener_socket = Arc::new(listener_socket);
+    let relay = Relay {
+        handover: Arc::new(Handover::new(relay_configs.handover_policy)),
+        relay_configs: Arc::new(relay_configs),
+        state,
+        timer: Instant::now(),
+    };
+    let Relay {
+        state,
+        relay_configs,
+        handover,
+        timer,
+    } = &relay;
+    let shutdown = &state.shutdown;
     let mut acceptor = None;
     loop {
         //need to move them for multi streamer
-        let (record_producer, record_consumer) = channel(BUFFER_LENGTH);
-        let streamer_socket = TcpListener::bind(relay_configs.streamer_address.clone())
-            .await
-            .unwrap();
-
+        let (frame_producer, _) = channel(FRAME_RING_LENGTH);
+        let (arrival_producer, arrival_receiver) = tokio::sync::mpsc::channel(BUFFER_LENGTH);
         let (streamer_alive_producer, streamer_alive_receiver) = tokio::sync::oneshot::channel();
-        let message_organizer_task: Option<JoinHandle<()>>;
-        let buffer_layer_task: Option<JoinHandle<()>>;
         let (listener_stream_tasks_producer, listener_stream_tasks_receiver) =
             tokio::sync::mpsc::channel(BUFFER_LENGTH);
         let mut new_streamer = Streamer {
             ip: "127.0.0.1".to_string().parse().unwrap(),
             port: 0000,
         };
-        let mut is_streaming = false;
-        match streamer_socket.accept().await {
+        let mut first_streamer = None;
+        let accepted = loop {
+            tokio::select! {
+                accepted = streamer_socket.accept() => break accepted,
+                off_air = listener_socket.accept() => match off_air {
+                    Ok((_, listener_info)) => {
+                        println!("{} Rejected | Reason -> Off Air", listener_info);
+                    }
+                    Err(err_val) => {
+                        eprintln!("Error: TCP Accept Listener | {}", err_val);
+                        tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
+                    }
+                },
+                _ = shutdown.cancelled() => {
+                    println!("Shutdown: Streamer Socket Closed | {:#?}", timer.elapsed());
+                    return;
+                }
+            }
+        };
+        match accepted {
+            Ok((_, streamer_info)) if is_banned(&state.banned_ips, streamer_info.ip()) => {
+                println!("Banned Streamer Rejected: {}", streamer_info);
+            }
             Ok((streamer_tcp, streamer_info)) => {
                 new_streamer.ip = streamer_info.ip();
                 new_streamer.port = streamer_info.port();
@@ -55,92 +157,261 @@ pub async fn start(relay_configs: Config) {
                 );
                 if relay_configs.tls {
                     acceptor = Some(tls_configurator().await);
-                    match acceptor.clone().unwrap().accept(streamer_tcp).await {
-                        Ok(streamer_tcp_tls) => {
-                            match tokio_tungstenite::accept_async(streamer_tcp_tls).await {
-                                Ok(ws_stream) => {
-                                    tokio::spawn(streamer_stream(
-                                        new_streamer.clone(),
-                                        record_producer,
-                                        ws_stream,
-                                        timer,
-                                        streamer_alive_producer,
-                                    ));
-                                    is_streaming = true;
-                                }
-                                Err(err_val) => {
-                                    eprintln!("Error: TCP to WS Transform | {}", err_val)
-                                }
-                            }
-                        }
-                        Err(err_val) => {
-                            eprintln!("Error: TCP TLS Streamer| {}", err_val);
-                        }
-                    }
-                } else {
-                    match tokio_tungstenite::accept_async(streamer_tcp).await {
-                        Ok(ws_stream) => {
-                            tokio::spawn(streamer_stream(
-                                new_streamer.clone(),
-                                record_producer,
-                                ws_stream,
-                                timer,
-                                streamer_alive_producer,
-                            ));
-                            is_streaming = true;
-                        }
-                        Err(err_val) => eprintln!("Error: TCP to WS Transform | {}", err_val),
-                    }
                 }
+                // a silent client must neither hold the port nor keep the relay from stopping
+                first_streamer = tokio::select! {
+                    first_streamer = first_streamer_handshake(
+                        new_streamer.clone(),
+                        streamer_tcp,
+                        acceptor.clone(),
+                        &relay,
+                    ) => first_streamer,
+                    _ = shutdown.cancelled() => {
+                        println!("Shutdown: Streamer Socket Closed | {:#?}", timer.elapsed());
+                        return;
+                    }
+                };
+            }
+            Err(err_val) => {
+                eprintln!("Error: TCP Accept Connection | {}", err_val);
+                tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
             }
-            Err(err_val) => eprintln!("Error: TCP Accept Connection | {}", err_val),
         }
 
-        if is_streaming {
-            let listener_socket = TcpListener::bind(relay_configs.listener_address.clone())
-                .await
-                .unwrap();
-            let (message_producer, message_consumer) = channel(BUFFER_LENGTH);
-            let (buffered_producer, _) = channel(BUFFER_LENGTH);
-            message_organizer_task = tokio::spawn(message_organizer(
-                message_producer.clone(),
-                record_consumer,
-                relay_configs.latency,
-            ))
-            .into();
-            buffer_layer_task = tokio::spawn(buffer_layer(
-                message_consumer,
-                buffered_producer.clone(),
-                relay_configs.latency,
-            ))
-            .into();
-            let (listener_socket_killer_producer, listener_socket_killer_receiver) =
-                tokio::sync::oneshot::channel();
+        if let Some(first_streamer) = first_streamer {
+            tokio::spawn(streamer_session(
+                first_streamer,
+                arrival_producer,
+                streamer_alive_producer,
+                relay.clone(),
+            ));
+            // the streamer port stays open while live, the handover policy decides on later ones
+            let busy_canceller = shutdown.child_token();
+            let busy_streamer_handler_task = tokio::spawn(busy_streamer_handler(
+                streamer_socket.clone(),
+                acceptor.clone(),
+                busy_canceller.clone(),
+                relay.clone(),
+            ));
+            state.mount_statistics.set_listener_bound(true);
+            let pacer_task = tokio::spawn(pacer(
+                arrival_receiver,
+                frame_producer.clone(),
+                Duration::from_millis(relay_configs.latency.into()),
+            ));
+            let listener_canceller = shutdown.child_token();
             let listener_handler_task = tokio::spawn(listener_handler(
-                listener_socket,
+                listener_socket.clone(),
                 acceptor.clone(),
-                buffered_producer.clone(),
+                frame_producer.clone(),
                 listener_stream_tasks_producer,
-                timer,
-                listener_socket_killer_receiver,
+                listener_canceller.clone(),
+                relay.clone(),
             ));
-            status_checker(
-                buffered_producer.clone(),
-                timer,
-                new_streamer,
-                streamer_alive_receiver,
-                message_organizer_task,
-                buffer_layer_task,
+            state.mount_statistics.set_pipeline_running(true);
+            let pipeline = Pipeline {
+                frame_producer,
                 listener_stream_tasks_receiver,
                 listener_handler_task,
-                listener_socket_killer_producer,
-                relay_configs.listener_address.clone(),
-            )
-            .await;
-            drop(streamer_socket);
+                pacer_task,
+                listener_canceller,
+            };
+            status_checker(pipeline, new_streamer, streamer_alive_receiver, &relay).await;
+            // sessions still in their handshake are spawned apart, they see this before offering
+            busy_canceller.cancel();
+            busy_streamer_handler_task.abort();
+            let _ = busy_streamer_handler_task.await;
+            handover.clear();
+            state.mixer.kick_cohosts();
+        }
+        if shutdown.is_cancelled() {
+            println!("Shutdown: Relay Stopped | {:#?}", timer.elapsed());
+            return;
         }
     }
 }
+async fn first_streamer_handshake(
+    new_streamer: Streamer,
+    streamer_tcp: TcpStream,
+    acceptor: Option<TlsAcceptor>,
+    relay: &Relay,
+) -> Option<Candidate> {
+    match acceptor {
+        Some(acceptor) => {
+            match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(streamer_tcp)).await {
+                Ok(Ok(streamer_tcp_tls)) => {
+                    first_streamer_websocket(new_streamer, streamer_tcp_tls, &relay.relay_configs)
+                        .await
+                }
+                Ok(Err(err_val)) => {
+                    eprintln!("Error: TCP TLS Streamer| {}", err_val);
+                    relay.state.mount_statistics.tls_handshake_failed();
+                    None
+                }
+                Err(_) => {
+                    eprintln!("Error: TCP TLS Streamer | Handshake Timed Out");
+                    relay.state.mount_statistics.tls_handshake_failed();
+                    None
+                }
+            }
+        }
+        None => first_streamer_websocket(new_streamer, streamer_tcp, &relay.relay_configs).await,
+    }
+}
+async fn first_streamer_websocket<
+    S: AsyncRead + AsyncWrite + std::marker::Unpin + Send + 'static,
+>(
+    new_streamer: Streamer,
+    stream: S,
+    relay_configs: &Config,
+) -> Option<Candidate> {
+    let mut handshake = Handshake::default();
+    match tokio::time::timeout(
+        HANDSHAKE_TIMEOUT,
+        tokio_tungstenite::accept_hdr_async_with_config(
+            stream,
+            handshake_recorder(&mut handshake),
+            Some(websocket_config(relay_configs.max_frame_size)),
+        ),
+    )
+    .await
+    {
+        Ok(Ok(ws_stream)) => match streamer_authorization(relay_configs, &handshake, false) {
+            Ok(authorization) => {
+                let authorization = authorization.unwrap_or_default();
+                Some(Candidate {
+                    streamer: new_streamer,
+                    priority: authorization.priority,
+                    show: authorization.show,
+                    ws_stream: Box::new(ws_stream),
+                })
+            }
+            Err(reason) => {
+                reject_streamer(&new_streamer, ws_stream, reason).await;
+                None
+            }
+        },
+        Ok(Err(err_val)) => {
+            eprintln!("Error: TCP to WS Transform | {}", err_val);
+            None
+        }
+        Err(_) => {
+            eprintln!("Error: TCP WS Streamer | Handshake Timed Out");
+            None
+        }
+    }
+}
+fn websocket_config(max_message_size: usize) -> WebSocketConfig {
+    WebSocketConfig::default()
+        .max_frame_size(Some(max_message_size))
+        .max_message_size(Some(max_message_size))
+}
+fn decompress_frame(data: &[u8]) -> Option<Vec<u8>> {
+    // capped, a tiny brotli bomb must not inflate into gigabytes
+    let mut uncompressed_data = vec![];
+    Decompressor::new(data, 4096)
+        .take(MAX_DECOMPRESSED_FRAME_SIZE + 1)
+        .read_to_end(&mut uncompressed_data)
+        .ok()?;
+    if uncompressed_data.len() as u64 > MAX_DECOMPRESSED_FRAME_SIZE {
+        return None;
+    }
+    Some(uncompressed_data)
+}
+fn is_valid_frame(uncompressed_data: &[u8]) -> bool {
+    // samples arrive as "+1234-0567..." text, every one of them led by its sign
+    matches!(uncompressed_data.first(), Some(b'+' | b'-'))
+        && uncompressed_data
+            .split(|single| matches!(single, b'+' | b'-'))
+            .skip(1)
+            .all(|digits| digits.len() <= MAX_PRECISION && digits.iter().all(u8::is_ascii_digit))
+}
+fn is_silent(uncompressed_data: &[u8]) -> bool {
+    uncompressed_data
+        .iter()
+        .all(|single| matches!(single, b'+' | b'-' | b'0'))
+}
+fn is_banned(banned_ips: &Mutex<HashSet<IpAddr>>, ip: IpAddr) -> bool {
+    banned_ips.lock().unwrap().contains(&ip)
+}
+fn is_ingress_exceeded(
+    ingress_window: &mut (Instant, usize),
+    arrival: Instant,
+    frame_length: usize,
+    max_ingress_bitrate: usize,
+) -> bool {
+    if arrival - ingress_window.0 >= INGRESS_WINDOW {
+        *ingress_window = (arrival, 0);
+    }
+    ingress_window.1 += frame_length;
+    ingress_window.1 * 8 > max_ingress_bitrate * 1000
+}
+fn query_parameter<'a>(query: Option<&'a str>, key: &str) -> Option<&'a str> {
+    query?
+        .split('&')
+        .filter_map(|parameter| parameter.split_once('='))
+        .find(|(name, _)| *name == key)
+        .map(|(_, value)| value)
+}
+// priority comes with the key, whatever the streamer claims about itself is not asked
+fn streamer_authorization(
+    relay_configs: &Config,
+    handshake: &Handshake,
+    is_mount_busy: bool,
+) -> Result<Option<Authorization>, &'static str> {
+    let token = query_parameter(handshake.query.as_deref(), "token");
+    // a scheduled mount takes the key of the slot on air, and the next slot's key to wait
+    // for its turn while the mount is busy
+    if relay_configs.schedule.has_mount(&relay_configs.mount) {
+        let now = unix_timestamp();
+        let schedule = &relay_configs.schedule;
+        return match (
+            schedule.on_air(&relay_configs.mount, now),
+            schedule.up_next(&relay_configs.mount, now, NEXT_SLOT_WINDOW),
+        ) {
+            (Some(show), _) if show.is_allowed(token) => Ok(Some(Authorization {
+                priority: SHOW_PRIORITY,
+                show: Some(show.clone()),
+            })),
+            (_, Some(show)) if is_mount_busy && show.is_allowed(token) => Ok(Some(Authorization {
+                priority: NEXT_SHOW_PRIORITY,
+                show: Some(show.clone()),
+            })),
+            (Some(_), _) => Err("Unauthorized Streamer"),
+            (None, _) => Err("Outside Scheduled Slot"),
+        };
+    }
+    if relay_configs.streamer_tokens.is_empty() {
+        return Ok(None);
+    }
+    match relay_configs
+        .streamer_tokens
+        .iter()
+        .find(|streamer_token| tokens_match(token, &streamer_token.token))
+    {
+        Some(streamer_token) => Ok(Some(Authorization {
+            priority: streamer_token.priority,
+            show: None,
+        })),
+        None => Err("Unauthorized Streamer"),
+    }
+}
+async fn reject_streamer<T: futures_util::Sink<Message> + std::marker::Unpin>(
+    streamer: &Streamer,
+    mut ws_stream: T,
+    reason: &'static str,
+) {
+    println!(
+        "Streamer {}:{} Rejected | Reason -> {}",
+        streamer.ip, streamer.port, reason
+    );
+    let _ = ws_stream
+        .send(Message::Close(Some(CloseFrame {
+            code: CloseCode::Policy,
+            reason: reason.into(),
+        })))
+        .await;
+}
 async fn tls_configurator() -> TlsAcceptor {
     let fullchain: io::Result<Vec<CertificateDer<'static>>> = certs(&mut BufReader::new(
         File::open("certificates/fullchain.pem").unwrap(),
@@ -159,267 +430,1112 @@ async fn tls_configurator() -> TlsAcceptor {
         .with_no_client_auth()
         .with_single_cert(fullchain, privkey)
         .unwrap();
-    let acceptor = TlsAcceptor::from(Arc::new(server_tls_config));
-    acceptor
+    TlsAcceptor::from(Arc::new(server_tls_config))
 }
 async fn listener_handler(
-    listener_socket: TcpListener,
+    listener_socket: Arc<TcpListener>,
     acceptor: Option<TlsAcceptor>,
-    buffered_producer: Sender<Message>,
+    frame_producer: Sender<Message>,
     listener_stream_tasks_producer: tokio::sync::mpsc::Sender<JoinHandle<()>>,
-    timer: Instant,
-    mut listener_socket_killer_receiver: tokio::sync::oneshot::Receiver<bool>,
+    listener_canceller: CancellationToken,
+    relay: Relay,
 ) {
-    while let Err(_) = listener_socket_killer_receiver.try_recv() {
-        match listener_socket.accept().await.no_block() {
-            Ok(accepted_request) => match accepted_request {
-                Some((tcp_stream, listener_info)) => {
-                    let new_listener = Listener {
-                        ip: listener_info.ip(),
-                        port: listener_info.port(),
-                    };
-                    match acceptor {
-                        Some(ref acceptor) => match acceptor.accept(tcp_stream).await {
-                            Ok(listener_tcp_tls) => {
-                                match tokio_tungstenite::accept_async(listener_tcp_tls).await {
-                                    Ok(wss_stream) => {
-                                        let listener_stream_task = tokio::spawn(stream(
-                                            new_listener,
-                                            wss_stream,
-                                            buffered_producer.subscribe(),
-                                        ));
-                                        let _ = listener_stream_tasks_producer
-                                            .send(listener_stream_task)
-                                            .await;
-                                    }
-                                    Err(err_val) => {
-                                        eprintln!("Error: TCP WSS Listener | {}", err_val);
-                                        drop(listener_socket);
-                                        return;
-                                    }
-                                }
-                            }
-                            Err(err_val) => {
-                                eprintln!("Error: TCP TLS Listener | {}", err_val);
-                                drop(listener_socket);
-                                return;
-                            }
-                        },
-                        None => match tokio_tungstenite::accept_async(tcp_stream).await {
-                            Ok(ws_stream) => {
-                                let listener_stream_task = tokio::spawn(stream(
-                                    new_listener,
-                                    ws_stream,
-                                    buffered_producer.subscribe(),
-                                ));
-                                let _ = listener_stream_tasks_producer
-                                    .send(listener_stream_task)
-                                    .await;
-                            }
-                            Err(err_val) => {
-                                eprintln!("Error: TCP WS Listener | {}", err_val);
-                                drop(listener_socket);
-                                return;
-                            }
-                        },
-                    }
-                    println!("New Listener: {} | {:#?}", listener_info, timer.elapsed());
+    let mut accept_window = (Instant::now(), 0_u16);
+    loop {
+        let (tcp_stream, listener_info) = tokio::select! {
+            accepted = listener_socket.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(err_val) => {
+                    eprintln!("Error: TCP Accept Listener | {}", err_val);
+                    tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
+                    continue;
                 }
-                None => {}
             },
-            Err(_) => {}
+            _ = listener_canceller.cancelled() => break,
+        };
+        if is_banned(&relay.state.banned_ips, listener_info.ip()) {
+            println!("Banned Listener Rejected: {}", listener_info);
+            continue;
+        }
+        let new_listener = Listener {
+            ip: listener_info.ip(),
+            port: listener_info.port(),
+        };
+        // a flood is dropped at the socket, no handshake is spent on it
+        if is_accept_rate_exceeded(&relay.relay_configs, &mut accept_window) {
+            println!(
+                "{} Rejected | Reason -> Accept Rate Exceeded",
+                listener_info
+            );
+            continue;
+        }
+        let listener_slot = relay.state.mount_statistics.reserve_listener(
+            listener_info.ip(),
+            relay.relay_configs.max_listeners,
+            relay.relay_configs.max_connections_per_ip,
+        );
+        println!(
+            "New Listener: {} | {:#?}",
+            listener_info,
+            relay.timer.elapsed()
+        );
+        let listener_stream_task = tokio::spawn(listener_connection(
+            tcp_stream,
+            new_listener,
+            acceptor.clone(),
+            listener_slot,
+            frame_producer.clone(),
+            relay.clone(),
+        ));
+        let _ = listener_stream_tasks_producer
+            .send(listener_stream_task)
+            .await;
+    }
+}
+async fn listener_connection(
+    tcp_stream: TcpStream,
+    listener: Listener,
+    acceptor: Option<TlsAcceptor>,
+    listener_slot: Result<ListenerSlot, &'static str>,
+    frame_producer: Sender<Message>,
+    relay: Relay,
+) {
+    match acceptor {
+        Some(acceptor) => {
+            match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(tcp_stream)).await {
+                Ok(Ok(listener_tcp_tls)) => {
+                    listener_websocket(
+                        listener_tcp_tls,
+                        "TCP WSS Listener",
+                        listener,
+                        listener_slot,
+                        frame_producer,
+                        relay,
+                    )
+                    .await
+                }
+                Ok(Err(err_val)) => {
+                    eprintln!("Error: TCP TLS Listener | {}", err_val);
+                    relay.state.mount_statistics.tls_handshake_failed();
+                }
+                Err(_) => {
+                    eprintln!("Error: TCP TLS Listener | Handshake Timed Out");
+                    relay.state.mount_statistics.tls_handshake_failed();
+                }
+            }
+        }
+        None => {
+            listener_websocket(
+                tcp_stream,
+                "TCP WS Listener",
+                listener,
+                listener_slot,
+                frame_producer,
+                relay,
+            )
+            .await
+        }
+    }
+}
+async fn listener_websocket<S: AsyncRead + AsyncWrite + std::marker::Unpin>(
+    stream: S,
+    error_label: &str,
+    listener: Listener,
+    listener_slot: Result<ListenerSlot, &'static str>,
+    frame_producer: Sender<Message>,
+    relay: Relay,
+) {
+    let mut stream = tokio::io::BufReader::new(stream);
+    let mut handshake = Handshake::default();
+    let accepted = tokio::time::timeout(HANDSHAKE_TIMEOUT, async {
+        // the limits are fixed before the upgrade, the request line tells a caller from a listener
+        let max_message_size = match is_call_request(stream.fill_buf().await?) {
+            true => CALL_MAX_MESSAGE_SIZE,
+            false => LISTENER_MAX_MESSAGE_SIZE,
+        };
+        tokio_tungstenite::accept_hdr_async_with_config(
+            stream,
+            handshake_recorder(&mut handshake),
+            Some(websocket_config(max_message_size)),
+        )
+        .await
+    })
+    .await;
+    match accepted {
+        Ok(Ok(ws_stream)) => {
+            listener_session(
+                listener,
+                handshake,
+                ws_stream,
+                listener_slot,
+                frame_producer,
+                relay,
+            )
+            .await
+        }
+        Ok(Err(err_val)) => eprintln!("Error: {} | {}", error_label, err_val),
+        Err(_) => eprintln!("Error: {} | Handshake Timed Out", error_label),
+    }
+}
+fn is_call_request(request_head: &[u8]) -> bool {
+    request_head
+        .strip_prefix(b"GET ")
+        .and_then(|target| target.strip_prefix(CALL_PATH.as_bytes()))
+        .is_some_and(|rest| matches!(rest.first(), Some(b' ' | b'?')))
+}
+// the callback signature is tungstenite's, its error type is not ours to shrink
+#[allow(clippy::result_large_err)]
+fn handshake_recorder(
+    handshake: &mut Handshake,
+) -> impl FnOnce(&Request, Response) -> Result<Response, ErrorResponse> + '_ {
+    move |request, response| {
+        handshake.user_agent = request
+            .headers()
+            .get(USER_AGENT)
+            .and_then(|user_agent| user_agent.to_str().ok())
+            .map(str::to_string);
+        handshake.path = request.uri().path().to_string();
+        handshake.query = request.uri().query().map(str::to_string);
+        Ok(response)
+    }
+}
+async fn listener_session<
+    T: futures_util::Stream<Item = Result<Message, Error>>
+        + futures_util::Sink<Message>
+        + std::marker::Unpin,
+>(
+    listener: Listener,
+    handshake: Handshake,
+    ws_stream: T,
+    listener_slot: Result<ListenerSlot, &'static str>,
+    frame_producer: Sender<Message>,
+    relay: Relay,
+) {
+    match listener_slot {
+        Err(reason) => reject_listener(listener, ws_stream, reason).await,
+        Ok(listener_slot) if handshake.path == CALL_PATH => {
+            drop(listener_slot);
+            call(listener, handshake, ws_stream, relay).await
+        }
+        Ok(listener_slot) => {
+            let listener_statistics = relay.state.mount_statistics.listener_connected(
+                listener_slot,
+                listener.clone(),
+                handshake.user_agent,
+            );
+            stream(
+                listener,
+                ws_stream,
+                frame_producer.subscribe(),
+                listener_statistics,
+                relay,
+            )
+            .await
         }
     }
-    drop(listener_socket);
+}
+fn is_accept_rate_exceeded(relay_configs: &Config, accept_window: &mut (Instant, u16)) -> bool {
+    if accept_window.0.elapsed() >= Duration::from_secs(1) {
+        *accept_window = (Instant::now(), 0);
+    }
+    accept_window.1 = accept_window.1.saturating_add(1);
+    accept_window.1 > relay_configs.max_accept_rate
+}
+async fn reject_listener<T: futures_util::Sink<Message> + std::marker::Unpin>(
+    listener: Listener,
+    mut ws_stream: T,
+    reason: &'static str,
+) {
+    println!(
+        "{}:{} Rejected | Reason -> {}",
+        listener.ip, listener.port, reason
+    );
+    let _ = ws_stream
+        .send(Message::Close(Some(CloseFrame {
+            code: CloseCode::Again,
+            reason: reason.into(),
+        })))
+        .await;
 }
 async fn status_checker(
-    buffered_producer: Sender<Message>,
-    timer: Instant,
+    pipeline: Pipeline,
     streamer: Streamer,
     mut streamer_alive_receiver: tokio::sync::oneshot::Receiver<bool>,
-    message_organizer_task: Option<JoinHandle<()>>,
-    buffer_layer_task: Option<JoinHandle<()>>,
-    mut listener_stream_tasks_receiver: tokio::sync::mpsc::Receiver<JoinHandle<()>>,
-    listener_handler_task: JoinHandle<()>,
-    listener_socket_killer_producer: tokio::sync::oneshot::Sender<bool>,
-    listener_address: String,
+    relay: &Relay,
 ) {
-    let mut listener_counter = buffered_producer.receiver_count();
+    let Pipeline {
+        frame_producer,
+        mut listener_stream_tasks_receiver,
+        listener_handler_task,
+        pacer_task,
+        listener_canceller,
+    } = pipeline;
+    let Relay {
+        state,
+        relay_configs,
+        timer,
+        ..
+    } = relay;
+    let mount_statistics = &state.mount_statistics;
+    let shutdown = &state.shutdown;
+    let mut listener_counter = frame_producer.receiver_count();
     let mut bottleneck_flag = false;
     loop {
-        tokio::time::sleep(Duration::from_secs(3)).await;
-        match streamer_alive_receiver.try_recv() {
-            Ok(_) => {
-                println!(
-                    "Cleaning: Streamer Disconnected | {}",
-                    format!("{}:{}", streamer.ip, streamer.port)
-                );
-                let cleaning_timer = Instant::now();
-                match message_organizer_task.as_ref() {
-                    Some(message_organizer_task) => message_organizer_task.abort(),
-                    None => {
-                        eprintln!("Error: Message Organizer Task -> None");
-                    }
-                }
-                match buffer_layer_task.as_ref() {
-                    Some(buffer_layer_task) => buffer_layer_task.abort(),
-                    None => {
-                        eprintln!("Error: Buffer Layer Task -> None");
-                    }
-                }
-                if let Err(_) = listener_socket_killer_producer.send(true) {
-                    eprintln!("Error: Cleaning | Socket Kill Failed, Receiver Dropped");
-                }
-                let mut listener_task_counter = 0;
-                while listener_stream_tasks_receiver.len() > 0 {
-                    match listener_stream_tasks_receiver.recv().await {
-                        Some(listener_stream_task) => {
-                            listener_stream_task.abort();
-                            listener_task_counter += 1;
-                        }
-                        None => {}
+        let is_streamer_gone = tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(3)) => false,
+            _ = &mut streamer_alive_receiver => true,
+            _ = shutdown.cancelled() => true,
+        };
+        let is_shutting_down = shutdown.is_cancelled();
+        if is_streamer_gone {
+            println!(
+                "Cleaning: Streamer Disconnected | {}:{}",
+                streamer.ip, streamer.port
+            );
+            let cleaning_timer = Instant::now();
+            listener_canceller.cancel();
+            if let Err(err_val) = listener_handler_task.await {
+                eprintln!("Error: Cleaning | Listener Handler | {}", err_val);
+            }
+            mount_statistics.set_listener_bound(false);
+            println!(
+                "Cleaning: Listener Handler Stopped | {}",
+                relay_configs.listener_address
+            );
+            pacer_task.abort();
+            let mut listener_task_counter = 0;
+            let drain_deadline = Instant::now() + SHUTDOWN_DRAIN_TIMEOUT;
+            while let Some(listener_stream_task) = listener_stream_tasks_receiver.recv().await {
+                if is_shutting_down {
+                    let listener_stream_task_aborter = listener_stream_task.abort_handle();
+                    if tokio::time::timeout_at(drain_deadline, listener_stream_task)
+                        .await
+                        .is_err()
+                    {
+                        listener_stream_task_aborter.abort();
                     }
+                } else {
+                    listener_stream_task.abort();
                 }
-                if !listener_handler_task.is_finished() {
-                    listener_handler_task.abort();
-                    println!("Cleaning: Listener Handler Killed");
-                }
-                while TcpListener::bind(listener_address.clone()).await.is_err() {
-                    tokio::time::sleep(Duration::from_millis(1)).await;
-                }
-                println!("Cleaning: Listener Socket Killed | {}", listener_address);
-                println!(
-                    "Cleaning Done: Streamer Disconnected | {} | Disconnected Listener(s) = {} | {:#?}",
-                    format!("{}:{}", streamer.ip, streamer.port),
+                listener_task_counter += 1;
+            }
+            mount_statistics.streamer_disconnected();
+            mount_statistics.set_pipeline_running(false);
+            println!(
+                    "Cleaning Done: Streamer Disconnected | {}:{} | Disconnected Listener(s) = {} | {:#?}",
+                    streamer.ip,
+                    streamer.port,
                     listener_task_counter,
                     cleaning_timer.elapsed()
                 );
-                return;
-            }
-            Err(_) => {}
+            return;
         }
 
-        if buffered_producer.receiver_count() != 0 {
-            if buffered_producer.len() > 2 {
+        mount_statistics.set_pipeline_running(
+            !listener_handler_task.is_finished() && !pacer_task.is_finished(),
+        );
+        mount_statistics.set_broadcast_lag(frame_producer.len());
+        if frame_producer.receiver_count() != 0 {
+            if frame_producer.len() > 2 {
                 bottleneck_flag = true;
+                mount_statistics.set_bottleneck(true);
                 println!(
                     "Bottleneck: {} | {:#?}",
-                    buffered_producer.len(),
+                    frame_producer.len(),
                     timer.elapsed()
                 );
             }
-            if bottleneck_flag && buffered_producer.len() < 2 {
+            if bottleneck_flag && frame_producer.len() < 2 {
                 bottleneck_flag = false;
+                mount_statistics.set_bottleneck(false);
                 println!("Flawless Again");
             }
-            if listener_counter != buffered_producer.receiver_count() {
-                listener_counter = buffered_producer.receiver_count();
-                println!("Listener(s): {}", listener_counter);
+        }
+        if listener_counter != frame_producer.receiver_count() {
+            let previous_listener_counter = listener_counter;
+            listener_counter = frame_producer.receiver_count();
+            println!("Listener(s): {}", listener_counter);
+            for &threshold in &relay_configs.listener_thresholds {
+                if previous_listener_counter < threshold && listener_counter >= threshold {
+                    mount_statistics.publish(RelayEventKind::ListenersAbove {
+                        threshold,
+                        listener_count: listener_counter,
+                    });
+                } else if previous_listener_counter >= threshold && listener_counter < threshold {
+                    mount_statistics.publish(RelayEventKind::ListenersBelow {
+                        threshold,
+                        listener_count: listener_counter,
+                    });
+                }
             }
         }
     }
 }
-async fn buffer_layer(
-    mut message_consumer: Receiver<Message>,
-    buffered_producer: Sender<Message>,
-    delay: u16,
+async fn pacer(
+    mut arrival_receiver: tokio::sync::mpsc::Receiver<(Instant, Message)>,
+    frame_producer: Sender<Message>,
+    latency: Duration,
 ) {
-    loop {
-        tokio::time::sleep(Duration::from_millis(delay.into())).await;
-        while message_consumer.len() > 0 {
-            match message_consumer.recv().await {
-                Ok(message) => match buffered_producer.send(message) {
-                    Ok(_) => {}
-                    Err(_) => {}
-                },
-                Err(_) => {}
-            }
-        }
+    // every frame leaves exactly `latency` after it arrived, however the streamer batches
+    while let Some((arrival, message)) = arrival_receiver.recv().await {
+        tokio::time::sleep_until(arrival + latency).await;
+        let _ = frame_producer.send(message);
     }
 }
 async fn streamer_stream<
-    T: futures_util::Stream<Item = Result<Message, Error>> + std::marker::Unpin,
+    T: futures_util::Stream<Item = Result<Message, Error>>
+        + futures_util::Sink<Message>
+        + std::marker::Unpin,
 >(
     streamer: Streamer,
-    record_producer: Sender<Message>,
+    arrival_producer: tokio::sync::mpsc::Sender<(Instant, Message)>,
     mut ws_stream: T,
-    timer: Instant,
-    streamer_alive_producer: tokio::sync::oneshot::Sender<bool>,
+    streamer_kicker: CancellationToken,
+    relay: &Relay,
 ) {
+    let Relay {
+        state,
+        relay_configs,
+        timer,
+        ..
+    } = relay;
+    let AppState {
+        mount_statistics,
+        chat,
+        song_requests,
+        call_in,
+        mixer,
+        ..
+    } = state;
+    let streamer_timeout = Duration::from_secs(relay_configs.streamer_timeout);
+    let silence_timeout = Duration::from_secs(relay_configs.silence_timeout);
+    let mut ping_interval = tokio::time::interval(STREAMER_PING_INTERVAL);
+    let mut last_seen = Instant::now();
+    let mut silent_since = None;
+    let mut ingress_window = (Instant::now(), 0);
+    let source_id = mixer.add_source(streamer.clone(), true, streamer_kicker.clone());
+    let mut chat_consumer = chat.subscribe();
+    let mut song_request_consumer = song_requests.subscribe();
+    let mut call_consumer = call_in.subscribe();
+    for pending_message in song_requests
+        .pending_messages()
+        .into_iter()
+        .chain(call_in.waiting_messages())
+    {
+        let _ = ws_stream.send(pending_message).await;
+    }
     loop {
-        match ws_stream.next().await {
-            Some(message_with_question) => {
-                match message_with_question {
-                    Ok(message) => {
-                        //println!("{}", message.len());
-                        match record_producer.send(message) {
-                            Ok(_) => {}
-                            Err(_) => {}
-                        }
-                    }
-                    Err(_) => {}
+        let message_with_question = tokio::select! {
+            message_with_question = ws_stream.next() => message_with_question,
+            _ = tokio::time::sleep_until(last_seen + streamer_timeout) => {
+                println!(
+                    "Streamer Stalled: {}:{} | {:#?}",
+                    streamer.ip,
+                    streamer.port,
+                    timer.elapsed()
+                );
+                None
+            }
+            _ = ping_interval.tick() => {
+                if ws_stream.send(Message::Ping(Default::default())).await.is_err() {
+                    None
+                } else {
+                    continue;
                 }
             }
-            None => {
+            chat_message = chat_consumer.recv() => match chat_message {
+                // the host reads along, missing a few lines under load is fine
+                Ok(chat_message) => {
+                    if ws_stream.send(chat_message).await.is_err() {
+                        None
+                    } else {
+                        continue;
+                    }
+                }
+                Err(_) => continue,
+            },
+            song_request = song_request_consumer.recv() => match song_request {
+                Ok(song_request) => {
+                    if ws_stream.send(song_request).await.is_err() {
+                        None
+                    } else {
+                        continue;
+                    }
+                }
+                Err(_) => continue,
+            },
+            call_message = call_consumer.recv() => match call_message {
+                Ok(call_message) => {
+                    if ws_stream.send(call_message).await.is_err() {
+                        None
+                    } else {
+                        continue;
+                    }
+                }
+                Err(_) => continue,
+            },
+            _ = streamer_kicker.cancelled() => {
                 println!(
-                    "Streamer Disconnected: {} | {:#?}",
-                    format!("{}:{}", streamer.ip, streamer.port),
+                    "Streamer Kicked: {}:{} | {:#?}",
+                    streamer.ip,
+                    streamer.port,
                     timer.elapsed()
                 );
-                streamer_alive_producer.send(false).unwrap();
-                return;
+                None
+            }
+        };
+        match message_with_question {
+            Some(Ok(Message::Ping(_) | Message::Pong(_))) => {}
+            Some(Ok(Message::Binary(data))) => {
+                //println!("{}", data.len());
+                let arrival = Instant::now();
+                mount_statistics.message_received(data.len());
+                if is_ingress_exceeded(
+                    &mut ingress_window,
+                    arrival,
+                    data.len(),
+                    relay_configs.max_ingress_bitrate,
+                ) {
+                    println!(
+                        "Streamer {}:{} Forced to Disconnect | Reason -> Ingress Bitrate Exceeded",
+                        streamer.ip, streamer.port
+                    );
+                    let _ = ws_stream
+                        .send(Message::Close(Some(CloseFrame {
+                            code: CloseCode::Policy,
+                            reason: "Ingress Bitrate Exceeded".into(),
+                        })))
+                        .await;
+                    break;
+                }
+                let uncompressed_data = match decompress_frame(&data) {
+                    Some(uncompressed_data) if is_valid_frame(&uncompressed_data) => {
+                        uncompressed_data
+                    }
+                    _ => {
+                        println!(
+                            "Invalid Frame: {}:{} | Reason -> Not Sample Data",
+                            streamer.ip, streamer.port
+                        );
+                        mount_statistics.frame_rejected();
+                        continue;
+                    }
+                };
+                last_seen = arrival;
+                if is_silent(&uncompressed_data) {
+                    let silent_since = *silent_since.get_or_insert(last_seen);
+                    if !mount_statistics.is_silent() && last_seen - silent_since >= silence_timeout
+                    {
+                        println!(
+                            "Silence Detected: {}:{} | {:#?}",
+                            streamer.ip,
+                            streamer.port,
+                            timer.elapsed()
+                        );
+                        mount_statistics.set_silence(true);
+                    }
+                } else if silent_since.take().is_some() && mount_statistics.is_silent() {
+                    println!(
+                        "Silence Over: {}:{} | {:#?}",
+                        streamer.ip,
+                        streamer.port,
+                        timer.elapsed()
+                    );
+                    mount_statistics.set_silence(false);
+                }
+                let frame = match mixer.mix(source_id, &uncompressed_data) {
+                    Some(mixed_data) => Message::Binary(mixed_data.into()),
+                    None => Message::Binary(data),
+                };
+                let _ = arrival_producer.send((last_seen, frame)).await;
+            }
+            Some(Ok(Message::Text(text))) => match serde_json::from_str(text.as_str()) {
+                Ok(StreamerMessage::Library { songs }) => song_requests.set_library(songs),
+                Ok(StreamerMessage::SongDecision { id, decision }) => {
+                    if let Some(song_request_status) = song_requests.decide(id, decision) {
+                        chat.announce(song_request_status);
+                    }
+                }
+                Ok(StreamerMessage::CallDecision { id, decision }) => call_in.decide(id, decision),
+                // plain text is still a title, as before
+                Err(_) if text.len() <= MAX_TITLE_LENGTH => {
+                    println!(
+                        "Now Playing: {} | {}:{}",
+                        text.as_str(),
+                        streamer.ip,
+                        streamer.port
+                    );
+                    mount_statistics.set_now_playing(text.to_string());
+                }
+                Err(_) => {
+                    println!(
+                        "Invalid Frame: {}:{} | Reason -> Not Sample Data or Title",
+                        streamer.ip, streamer.port
+                    );
+                    mount_statistics.frame_rejected();
+                }
+            },
+            Some(Ok(Message::Close(_))) | None => break,
+            Some(Err(err_val)) => {
+                eprintln!("Error: Streamer Stream | {}", err_val);
+                break;
+            }
+            Some(Ok(_)) => {
+                println!(
+                    "Invalid Frame: {}:{} | Reason -> Not Sample Data or Title",
+                    streamer.ip, streamer.port
+                );
+                mount_statistics.frame_rejected();
             }
         }
     }
+    let _ = ws_stream.close().await;
+    mixer.remove_source(source_id);
+    println!(
+        "Streamer Disconnected: {}:{} | {:#?}",
+        streamer.ip,
+        streamer.port,
+        timer.elapsed()
+    );
+}
+async fn slot_ender(show: Show, streamer_kicker: CancellationToken) {
+    tokio::time::sleep(Duration::from_secs(show.remaining(unix_timestamp()))).await;
+    println!("Slot Over: {} | {}", show.name, show.mount);
+    streamer_kicker.cancel();
+}
+// a standby was let in for the slot it held a key to then, by now that may be over or
+// only just beginning
+#[allow(clippy::result_large_err)]
+fn promotion_authorization(
+    relay_configs: &Config,
+    candidate: Candidate,
+) -> Result<Candidate, (Candidate, &'static str)> {
+    let Some(show) = &candidate.show else {
+        return Ok(candidate);
+    };
+    match relay_configs
+        .schedule
+        .on_air(&relay_configs.mount, unix_timestamp())
+    {
+        Some(on_air) if on_air == show => Ok(Candidate {
+            priority: SHOW_PRIORITY,
+            ..candidate
+        }),
+        _ => Err((candidate, "Outside Scheduled Slot")),
+    }
+}
+async fn streamer_session(
+    mut candidate: Candidate,
+    arrival_producer: tokio::sync::mpsc::Sender<(Instant, Message)>,
+    streamer_alive_producer: tokio::sync::oneshot::Sender<bool>,
+    relay: Relay,
+) {
+    let Relay {
+        state,
+        relay_configs,
+        handover,
+        timer,
+    } = &relay;
+    let mount_statistics = &state.mount_statistics;
+    let shutdown = &state.shutdown;
+    let mut streamer_kicker = shutdown.child_token();
+    mount_statistics.streamer_connected(candidate.streamer.clone(), streamer_kicker.clone());
+    loop {
+        handover.on_air(candidate.priority, streamer_kicker.clone());
+        mount_statistics.set_show(candidate.show.as_ref().map(|show| show.name.clone()));
+        let slot_ender = candidate
+            .show
+            .as_ref()
+            .map(|show| tokio::spawn(slot_ender(show.clone(), streamer_kicker.clone())));
+        streamer_stream(
+            candidate.streamer.clone(),
+            arrival_producer.clone(),
+            candidate.ws_stream,
+            streamer_kicker,
+            &relay,
+        )
+        .await;
+        if let Some(slot_ender) = slot_ender {
+            slot_ender.abort();
+        }
+        handover.off_air();
+        if shutdown.is_cancelled() {
+            break;
+        }
+        // the next one feeds the same pacer, listeners never notice the switch
+        let previous_streamer = candidate.streamer;
+        let next_candidate = loop {
+            let Some(next_candidate) = handover.next().await else {
+                break None;
+            };
+            match promotion_authorization(relay_configs, next_candidate) {
+                Ok(next_candidate) => break Some(next_candidate),
+                Err((next_candidate, reason)) => {
+                    reject_streamer(&next_candidate.streamer, next_candidate.ws_stream, reason)
+                        .await
+                }
+            }
+        };
+        candidate = match next_candidate {
+            Some(next_candidate) => next_candidate,
+            None => break,
+        };
+        println!(
+            "Handover: {}:{} -> {}:{} | {:#?}",
+            previous_streamer.ip,
+            previous_streamer.port,
+            candidate.streamer.ip,
+            candidate.streamer.port,
+            timer.elapsed()
+        );
+        streamer_kicker = shutdown.child_token();
+        mount_statistics.streamer_handed_over(candidate.streamer.clone(), streamer_kicker.clone());
+    }
+    state.call_in.hang_up_all();
+    state.mixer.kick_cohosts();
+    state.mixer.clear_clips();
+    let _ = streamer_alive_producer.send(false);
 }
 
-async fn message_organizer(
-    message_producer: Sender<Message>,
-    mut record_consumer: Receiver<Message>,
-    delay: u16,
+async fn busy_streamer_handler(
+    streamer_socket: Arc<TcpListener>,
+    acceptor: Option<TlsAcceptor>,
+    busy_canceller: CancellationToken,
+    relay: Relay,
+) {
+    loop {
+        let (streamer_tcp, streamer_info) = match streamer_socket.accept().await {
+            Ok(accepted) => accepted,
+            Err(err_val) => {
+                eprintln!("Error: TCP Accept Connection | {}", err_val);
+                tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
+                continue;
+            }
+        };
+        if is_banned(&relay.state.banned_ips, streamer_info.ip()) {
+            println!("Banned Streamer Rejected: {}", streamer_info);
+            continue;
+        }
+        let busy_streamer = Streamer {
+            ip: streamer_info.ip(),
+            port: streamer_info.port(),
+        };
+        println!(
+            "New Streamer: {} | Mount Busy | {:#?}",
+            streamer_info,
+            relay.timer.elapsed()
+        );
+        let relay = relay.clone();
+        let session_canceller = busy_canceller.child_token();
+        match acceptor.clone() {
+            Some(acceptor) => {
+                tokio::spawn(async move {
+                    match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(streamer_tcp))
+                        .await
+                    {
+                        Ok(Ok(streamer_tcp_tls)) => {
+                            busy_streamer_session(
+                                busy_streamer,
+                                streamer_tcp_tls,
+                                session_canceller,
+                                relay,
+                            )
+                            .await
+                        }
+                        Ok(Err(err_val)) => {
+                            eprintln!("Error: TCP TLS Streamer| {}", err_val);
+                            relay.state.mount_statistics.tls_handshake_failed();
+                        }
+                        Err(_) => {
+                            eprintln!("Error: TCP TLS Streamer | Handshake Timed Out");
+                            relay.state.mount_statistics.tls_handshake_failed();
+                        }
+                    }
+                });
+            }
+            None => {
+                tokio::spawn(busy_streamer_session(
+                    busy_streamer,
+                    streamer_tcp,
+                    session_canceller,
+                    relay,
+                ));
+            }
+        }
+    }
+}
+async fn busy_streamer_session<S: AsyncRead + AsyncWrite + std::marker::Unpin + Send + 'static>(
+    busy_streamer: Streamer,
+    stream: S,
+    session_canceller: CancellationToken,
+    relay: Relay,
+) {
+    let relay_configs = &relay.relay_configs;
+    let handover = &relay.handover;
+    let mut handshake = Handshake::default();
+    match tokio::time::timeout(
+        HANDSHAKE_TIMEOUT,
+        tokio_tungstenite::accept_hdr_async_with_config(
+            stream,
+            handshake_recorder(&mut handshake),
+            Some(websocket_config(relay_configs.max_frame_size)),
+        ),
+    )
+    .await
+    {
+        Ok(Ok(ws_stream)) => match streamer_authorization(relay_configs, &handshake, true) {
+            Err(reason) => reject_streamer(&busy_streamer, ws_stream, reason).await,
+            // without a key anyone could talk over the show or queue up to take it
+            Ok(None) if handover.policy() == HandoverPolicy::Cohost => {
+                reject_streamer(&busy_streamer, ws_stream, "Co-Hosting Disabled").await
+            }
+            Ok(None) if handover.policy() != HandoverPolicy::Reject => {
+                reject_streamer(&busy_streamer, ws_stream, "Stream Key Required").await
+            }
+            Ok(authorization) => {
+                let authorization = authorization.unwrap_or_default();
+                let candidate = Candidate {
+                    streamer: busy_streamer,
+                    priority: authorization.priority,
+                    show: authorization.show,
+                    ws_stream: Box::new(ws_stream) as BoxedStreamerSocket,
+                };
+                // the next slot's host waits for its turn whatever the policy
+                if handover.policy() == HandoverPolicy::Cohost && !candidate.is_up_next() {
+                    cohost_stream(
+                        candidate.streamer,
+                        candidate.ws_stream,
+                        session_canceller,
+                        &relay,
+                    )
+                    .await
+                } else if let Err((candidate, reason)) =
+                    handover.offer(candidate, &session_canceller)
+                {
+                    reject_streamer(&candidate.streamer, candidate.ws_stream, reason).await
+                }
+            }
+        },
+        Ok(Err(err_val)) => eprintln!("Error: TCP to WS Transform | {}", err_val),
+        Err(_) => eprintln!("Error: TCP WS Streamer | Handshake Timed Out"),
+    }
+}
+async fn cohost_stream<
+    T: futures_util::Stream<Item = Result<Message, Error>>
+        + futures_util::Sink<Message>
+        + std::marker::Unpin,
+>(
+    cohost: Streamer,
+    mut ws_stream: T,
+    cohost_kicker: CancellationToken,
+    relay: &Relay,
 ) {
+    let Relay {
+        state,
+        relay_configs,
+        timer,
+        ..
+    } = relay;
+    let AppState {
+        mount_statistics,
+        chat,
+        mixer,
+        ..
+    } = state;
+    let source_id = mixer.add_source(cohost.clone(), false, cohost_kicker.clone());
+    let streamer_timeout = Duration::from_secs(relay_configs.streamer_timeout);
+    let mut ping_interval = tokio::time::interval(STREAMER_PING_INTERVAL);
+    let mut last_seen = Instant::now();
+    let mut ingress_window = (Instant::now(), 0);
+    let mut chat_consumer = chat.subscribe();
+    println!(
+        "Co-Host Joined: {}:{} | {:#?}",
+        cohost.ip,
+        cohost.port,
+        timer.elapsed()
+    );
     loop {
-        match record_consumer.recv().await {
-            Ok(single_message) => match message_producer.send(single_message) {
-                Ok(_) => {}
-                Err(_) => {}
+        let message_with_question = tokio::select! {
+            message_with_question = ws_stream.next() => message_with_question,
+            _ = tokio::time::sleep_until(last_seen + streamer_timeout) => {
+                println!(
+                    "Co-Host Stalled: {}:{} | {:#?}",
+                    cohost.ip,
+                    cohost.port,
+                    timer.elapsed()
+                );
+                None
+            }
+            _ = ping_interval.tick() => {
+                if ws_stream.send(Message::Ping(Default::default())).await.is_err() {
+                    None
+                } else {
+                    continue;
+                }
+            }
+            chat_message = chat_consumer.recv() => match chat_message {
+                Ok(chat_message) => {
+                    if ws_stream.send(chat_message).await.is_err() {
+                        None
+                    } else {
+                        continue;
+                    }
+                }
+                Err(_) => continue,
             },
-            Err(_) => {}
+            _ = cohost_kicker.cancelled() => {
+                println!(
+                    "Co-Host Kicked: {}:{} | {:#?}",
+                    cohost.ip,
+                    cohost.port,
+                    timer.elapsed()
+                );
+                None
+            }
+        };
+        match message_with_question {
+            Some(Ok(Message::Binary(data))) => {
+                let arrival = Instant::now();
+                if is_ingress_exceeded(
+                    &mut ingress_window,
+                    arrival,
+                    data.len(),
+                    relay_configs.max_ingress_bitrate,
+                ) {
+                    println!(
+                        "Co-Host {}:{} Forced to Disconnect | Reason -> Ingress Bitrate Exceeded",
+                        cohost.ip, cohost.port
+                    );
+                    let _ = ws_stream
+                        .send(Message::Close(Some(CloseFrame {
+                            code: CloseCode::Policy,
+                            reason: "Ingress Bitrate Exceeded".into(),
+                        })))
+                        .await;
+                    break;
+                }
+                match decompress_frame(&data) {
+                    Some(uncompressed_data) if is_valid_frame(&uncompressed_data) => {
+                        last_seen = arrival;
+                        mixer.feed(source_id, &uncompressed_data);
+                    }
+                    _ => {
+                        println!(
+                            "Invalid Frame: {}:{} | Reason -> Not Sample Data",
+                            cohost.ip, cohost.port
+                        );
+                        mount_statistics.frame_rejected();
+                    }
+                }
+            }
+            Some(Ok(Message::Close(_))) | None => break,
+            Some(Err(err_val)) => {
+                eprintln!("Error: Co-Host Stream | {}", err_val);
+                break;
+            }
+            // titles, library and decisions stay with the primary streamer
+            Some(Ok(_)) => {}
         }
-        tokio::time::sleep(Duration::from_millis(delay.into())).await;
     }
+    let _ = ws_stream.close().await;
+    mixer.remove_source(source_id);
+    println!(
+        "Co-Host Left: {}:{} | {:#?}",
+        cohost.ip,
+        cohost.port,
+        timer.elapsed()
+    );
 }
-async fn stream<T: futures_util::Sink<Message> + std::marker::Unpin>(
+
+async fn stream<
+    T: futures_util::Stream<Item = Result<Message, Error>>
+        + futures_util::Sink<Message>
+        + std::marker::Unpin,
+>(
     listener: Listener,
     mut ws_stream: T,
-    mut buffered_consumer: Receiver<Message>,
+    mut frame_consumer: Receiver<Message>,
+    listener_statistics: Arc<ListenerStatistics>,
+    relay: Relay,
 ) {
-    while let Ok(message) = buffered_consumer.recv().await {
-        if buffered_consumer.len() > MAX_TOLERATED_MESSAGE_COUNT {
+    let AppState {
+        mount_statistics,
+        chat,
+        song_requests,
+        shutdown,
+        ..
+    } = &relay.state;
+    let listener_kicker = listener_statistics.kicked();
+    let mut chat_consumer = chat.subscribe();
+    let mut chat_session = ChatSession::new();
+    let reason = loop {
+        let message = tokio::select! {
+            message = frame_consumer.recv() => match message {
+                Ok(message) => message,
+                Err(RecvError::Lagged(_)) => {
+                    println!("{}:{} Forced to Disconnect | Reason -> Slow Consumer", listener.ip, listener.port);
+                    mount_statistics.slow_consumer_disconnected();
+                    break DisconnectReason::SlowConsumer;
+                }
+                Err(RecvError::Closed) => break DisconnectReason::StreamerGone,
+            },
+            request = ws_stream.next() => match request {
+                Some(Ok(Message::Text(request))) => {
+                    let reply = match serde_json::from_str(request.as_str()) {
+                        Ok(ListenerRequest::SongRequest { song }) => {
+                            Some(song_requests.request(listener.ip, song))
+                        }
+                        Ok(ListenerRequest::Library) => Some(song_requests.library_message()),
+                        Ok(ListenerRequest::Quality(quality)) => {
+                            listener_statistics.set_quality(quality);
+                            None
+                        }
+                        Ok(listener_request) => chat.handle(&mut chat_session, listener_request),
+                        Err(_) => Some(relay_error("Unknown Request")),
+                    };
+                    match reply {
+                        Some(reply) => reply,
+                        None => continue,
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => {
+                    println!("{}:{} is Disconnected", listener.ip, listener.port);
+                    break DisconnectReason::Left;
+                }
+                Some(Ok(_)) => continue,
+            },
+            chat_message = chat_consumer.recv() => match chat_message {
+                Ok(chat_message) => chat_message,
+                // audio comes first, a lagging listener just misses some chat
+                Err(_) => continue,
+            },
+            _ = listener_kicker.cancelled() => {
+                println!("{}:{} Forced to Disconnect | Reason -> Kicked", listener.ip, listener.port);
+                let _ = ws_stream
+                    .send(Message::Close(Some(CloseFrame {
+                        code: CloseCode::Policy,
+                        reason: "Kicked".into(),
+                    })))
+                    .await;
+                break DisconnectReason::Kicked;
+            }
+            _ = shutdown.cancelled() => {
+                let _ = ws_stream
+                    .send(Message::Close(Some(CloseFrame {
+                        code: CloseCode::Restart,
+                        reason: "Server Restarting".into(),
+                    })))
+                    .await;
+                break DisconnectReason::Shutdown;
+            }
+        };
+        listener_statistics.set_queue_depth(frame_consumer.len());
+        if frame_consumer.len() > MAX_TOLERATED_MESSAGE_COUNT {
             println!(
-                "{} Forced to Disconnect | Reason -> Slow Consumer",
-                format!("{}:{}", listener.ip, listener.port)
+                "{}:{} Forced to Disconnect | Reason -> Slow Consumer",
+                listener.ip, listener.port
             );
-            break;
+            mount_statistics.slow_consumer_disconnected();
+            break DisconnectReason::SlowConsumer;
         }
 
+        let message_length = message.len();
         match ws_stream.send(message).await {
             Ok(_) => {
-                if let Err(_) = ws_stream.flush().await {
-                    println!(
-                        "{} is Disconnected",
-                        format!("{}:{}", listener.ip, listener.port)
-                    );
-                    break;
+                if ws_stream.flush().await.is_err() {
+                    println!("{}:{} is Disconnected", listener.ip, listener.port);
+                    break DisconnectReason::Left;
                 }
+                mount_statistics.message_sent(&listener_statistics, message_length);
             }
             Err(_) => {
-                println!(
-                    "{} is Disconnected",
-                    format!("{}:{}", listener.ip, listener.port)
-                );
+                println!("{}:{} is Disconnected", listener.ip, listener.port);
+                break DisconnectReason::Left;
+            }
+        }
+    };
+    mount_statistics.listener_disconnected(&listener, reason);
+}
+
+async fn call<
+    T: futures_util::Stream<Item = Result<Message, Error>>
+        + futures_util::Sink<Message>
+        + std::marker::Unpin,
+>(
+    listener: Listener,
+    handshake: Handshake,
+    mut ws_stream: T,
+    relay: Relay,
+) {
+    let AppState {
+        call_in, shutdown, ..
+    } = &relay.state;
+    let query = handshake.query.as_deref();
+    if !call_in.is_authorized(query_parameter(query, "token")) {
+        reject_listener(listener, ws_stream, "Unauthorized Caller").await;
+        return;
+    }
+    let name = match query_parameter(query, "name") {
+        Some(name) if is_valid_nick(name) => name.to_string(),
+        _ => format!("{}:{}", listener.ip, listener.port),
+    };
+    let (id, mut on_air, hang_up) = call_in.join(listener.clone(), name);
+    let _ = ws_stream
+        .send(relay_message(&RelayMessage::CallStatus {
+            id,
+            on_air: false,
+        }))
+        .await;
+    loop {
+        tokio::select! {
+            message = ws_stream.next() => match message {
+                // frames from a caller still waiting are dropped, not buffered
+                Some(Ok(Message::Binary(data))) if *on_air.borrow() => {
+                    match decompress_frame(&data) {
+                        Some(uncompressed_data) if is_valid_frame(&uncompressed_data) => {
+                            call_in.forward(Message::Binary(data))
+                        }
+                        _ => println!(
+                            "Invalid Frame: {}:{} | Reason -> Not Sample Data",
+                            listener.ip, listener.port
+                        ),
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            changed = on_air.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                let on_air = *on_air.borrow();
+                if ws_stream
+                    .send(relay_message(&RelayMessage::CallStatus { id, on_air }))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+            _ = hang_up.cancelled() => {
+                let _ = ws_stream
+                    .send(Message::Close(Some(CloseFrame {
+                        code: CloseCode::Normal,
+                        reason: "Hung Up".into(),
+                    })))
+                    .await;
+                break;
+            }
+            _ = shutdown.cancelled() => {
+                let _ = ws_stream
+                    .send(Message::Close(Some(CloseFrame {
+                        code: CloseCode::Restart,
+                        reason: "Server Restarting".into(),
+                    })))
+                    .await;
                 break;
             }
         }
     }
+    call_in.leave(id);
 }
@@ -1,6 +1,10 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use subtle::ConstantTimeEq;
 use tokio::{fs::File, io::AsyncReadExt};
+use tokio_util::sync::CancellationToken;
 
-use crate::Config;
+use crate::{interstitials, schedule::Schedule, Config, HandoverPolicy, StreamerToken};
 
 pub async fn get_config() -> Config {
     let mut config_file = File::open("configs/relay_configs.txt").await.unwrap();
@@ -23,5 +27,124 @@ pub async fn get_config() -> Config {
         streamer_address: configs_cleaned[2].to_string(),
         latency: configs_cleaned[3].parse().unwrap(),
         tls: configs_cleaned[4].parse().unwrap(),
+        mount: configs_cleaned[5].to_string(),
+        admin_token: match configs_cleaned[6] {
+            "none" => None,
+            admin_token => Some(admin_token.to_string()),
+        },
+        max_listeners: configs_cleaned[7].parse().unwrap(),
+        max_connections_per_ip: configs_cleaned[8].parse().unwrap(),
+        max_accept_rate: configs_cleaned[9].parse().unwrap(),
+        streamer_timeout: configs_cleaned[10].parse().unwrap(),
+        silence_timeout: configs_cleaned[11].parse().unwrap(),
+        max_frame_size: configs_cleaned[12].parse().unwrap(),
+        max_ingress_bitrate: configs_cleaned[13].parse().unwrap(),
+        listener_thresholds: configs_cleaned[14]
+            .split(',')
+            .map(|listener_threshold| listener_threshold.trim().parse().unwrap())
+            .collect(),
+        webhook_urls: configs_cleaned[15]
+            .split(',')
+            .map(|webhook_url| webhook_url.trim().to_string())
+            .filter(|webhook_url| webhook_url != "none")
+            .collect(),
+        session_log: configs_cleaned[16].to_string(),
+        history_log: match configs_cleaned[17] {
+            "none" => None,
+            history_log => Some(history_log.to_string()),
+        },
+        max_chat_length: configs_cleaned[18].parse().unwrap(),
+        max_chat_rate: configs_cleaned[19].parse().unwrap(),
+        max_song_request_rate: configs_cleaned[20].parse().unwrap(),
+        call_token: match configs_cleaned[21] {
+            "none" => None,
+            call_token => Some(call_token.to_string()),
+        },
+        streamer_tokens: configs_cleaned[22]
+            .split(',')
+            .map(|streamer_token| streamer_token.trim())
+            .filter(|streamer_token| *streamer_token != "none")
+            .map(|streamer_token| match streamer_token.rsplit_once(':') {
+                Some((token, priority)) => StreamerToken {
+                    token: token.to_string(),
+                    priority: priority.parse().unwrap(),
+                },
+                None => StreamerToken {
+                    token: streamer_token.to_string(),
+                    priority: 0,
+                },
+            })
+            .collect(),
+        handover_policy: match configs_cleaned[23] {
+            "reject" => HandoverPolicy::Reject,
+            "standby" => HandoverPolicy::Standby,
+            "priority" => HandoverPolicy::Priority,
+            _ => HandoverPolicy::Cohost,
+        },
+        schedule: match configs_cleaned[24] {
+            "none" => Schedule::default(),
+            schedule => Schedule::load(schedule).await,
+        },
+        stream_sample_rate: configs_cleaned[25].parse().unwrap(),
+        stream_channels: configs_cleaned[26].parse().unwrap(),
+        interstitials: match configs_cleaned[27] {
+            "none" => vec![],
+            interstitials => {
+                interstitials::load(
+                    interstitials,
+                    configs_cleaned[25].parse().unwrap(),
+                    configs_cleaned[26].parse().unwrap(),
+                )
+                .await
+            }
+        },
+        front_dir: match configs_cleaned[28] {
+            "none" => None,
+            front_dir => Some(front_dir.to_string()),
+        },
+        public_address: match configs_cleaned[29] {
+            "none" => None,
+            public_address => Some(public_address.to_string()),
+        },
+    }
+}
+
+// keys and tokens come from anyone who can connect, how long a compare takes must not tell them apart
+pub fn tokens_match(given: Option<&str>, expected: &str) -> bool {
+    given.is_some_and(|given| given.as_bytes().ct_eq(expected.as_bytes()).into())
+}
+
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or_default()
+}
+
+pub async fn shutdown_signal(shutdown: CancellationToken) {
+    let interrupt = async {
+        if let Err(err_val) = tokio::signal::ctrl_c().await {
+            eprintln!("Error: Signal | SIGINT | {}", err_val);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate_signal) => {
+                terminate_signal.recv().await;
+            }
+            Err(err_val) => {
+                eprintln!("Error: Signal | SIGTERM | {}", err_val);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = interrupt => println!("Shutdown: SIGINT"),
+        _ = terminate => println!("Shutdown: SIGTERM"),
     }
+    shutdown.cancel();
 }
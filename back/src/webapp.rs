@@ -0,0 +1,82 @@
+use std::{io, net::SocketAddr, path::Path};
+
+use crate::Config;
+
+pub const WEB_APP_PATH: &str = "/listen";
+
+// the built front bundle, handed the relay's addresses so it never needs them hard-coded
+#[derive(Debug, Clone)]
+pub struct WebApp {
+    pub dir: String,
+    public_address: Option<String>,
+    http_port: u16,
+    listener_port: u16,
+    tls: bool,
+}
+
+impl WebApp {
+    pub fn new(relay_configs: &Config) -> Option<Self> {
+        let dir = relay_configs.front_dir.clone()?;
+        if !Path::new(&dir).join("index.html").is_file() {
+            eprintln!("Error: Web App | {} | No index.html", dir);
+        }
+        Some(WebApp {
+            dir,
+            public_address: relay_configs.public_address.clone(),
+            http_port: port(&relay_configs.axum_address)?,
+            listener_port: port(&relay_configs.listener_address)?,
+            tls: relay_configs.tls,
+        })
+    }
+
+    // without a configured public address the page is pointed back at whatever host it came from
+    pub async fn index(&self, host: Option<&str>) -> io::Result<String> {
+        let index = tokio::fs::read_to_string(Path::new(&self.dir).join("index.html")).await?;
+        let host = match (&self.public_address, host.map(host_name)) {
+            (Some(public_address), _) => public_address.as_str(),
+            (None, Some(host)) if is_valid_host(host) => host,
+            (None, _) => "127.0.0.1",
+        };
+        let (http_scheme, ws_scheme) = match self.tls {
+            true => ("https", "wss"),
+            false => ("http", "ws"),
+        };
+        let relay_meta = format!(
+            "<meta name=\"radioxide-relay\" content=\"{}://{}:{}\">\
+             <meta name=\"radioxide-listener\" content=\"{}://{}:{}\">",
+            http_scheme, host, self.http_port, ws_scheme, host, self.listener_port
+        );
+        Ok(match index.find("</head>") {
+            Some(head_end) => format!("{}{}{}", &index[..head_end], relay_meta, &index[head_end..]),
+            None => format!("{}{}", relay_meta, index),
+        })
+    }
+}
+
+fn port(address: &str) -> Option<u16> {
+    match address.parse::<SocketAddr>() {
+        Ok(address) => Some(address.port()),
+        Err(err_val) => {
+            eprintln!("Error: Web App | {} | {}", address, err_val);
+            None
+        }
+    }
+}
+
+fn host_name(host: &str) -> &str {
+    match host.strip_prefix('[') {
+        Some(ipv6) => ipv6
+            .find(']')
+            .map(|bracket| &host[..bracket + 2])
+            .unwrap_or(host),
+        None => host.split(':').next().unwrap_or(host),
+    }
+}
+
+// the host header lands inside the page, anything that is not a plain host is ignored
+fn is_valid_host(host: &str) -> bool {
+    !host.is_empty()
+        && host
+            .chars()
+            .all(|single| single.is_ascii_alphanumeric() || ".-:[]".contains(single))
+}
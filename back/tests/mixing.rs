@@ -0,0 +1,20 @@
+use back::{mixing::Mixer, Streamer};
+use tokio_util::sync::CancellationToken;
+
+fn streamer(port: u16) -> Streamer {
+    serde_json::from_str(&format!("{{\"ip\":\"127.0.0.1\",\"port\":{}}}", port)).unwrap()
+}
+
+#[test]
+fn oversized_samples_do_not_poison_the_mixer() {
+    let mixer = Mixer::new();
+    let primary = mixer.add_source(streamer(1), true, CancellationToken::new());
+    let cohost = mixer.add_source(streamer(2), false, CancellationToken::new());
+    let oversized = format!("+{}-0500", "9".repeat(40));
+    mixer.feed(cohost, oversized.as_bytes());
+    assert!(mixer.set_gain(primary, 0.5));
+    assert!(mixer.mix(primary, oversized.as_bytes()).is_some());
+    // still usable afterwards
+    assert!(mixer.mix(primary, b"+0500-0500").is_some());
+    assert_eq!(mixer.report().len(), 2);
+}
@@ -0,0 +1,433 @@
+use std::time::Duration;
+
+use back::{server::RelayServer, Config, HandoverPolicy, StreamerToken};
+use futures_util::{SinkExt, StreamExt};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+const SETTLE: Duration = Duration::from_millis(300);
+
+fn session_log(name: &str) -> String {
+    std::env::temp_dir()
+        .join(format!("radioxide-{}-{}.jsonl", name, std::process::id()))
+        .to_string_lossy()
+        .to_string()
+}
+
+type Socket =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+async fn connect_streamer(address: std::net::SocketAddr, query: &str) -> (Socket, u16) {
+    let (mut streamer, _) = connect_async(format!("ws://{}/?{}", address, query))
+        .await
+        .unwrap();
+    let port = match streamer.get_ref() {
+        tokio_tungstenite::MaybeTlsStream::Plain(tcp_stream) => {
+            tcp_stream.local_addr().unwrap().port()
+        }
+        _ => unreachable!(),
+    };
+    streamer.send(Message::text("Test Title")).await.unwrap();
+    tokio::time::sleep(SETTLE).await;
+    (streamer, port)
+}
+
+async fn close_reason(socket: &mut Socket) -> String {
+    loop {
+        match socket.next().await {
+            Some(Ok(Message::Close(Some(close_frame)))) => return close_frame.reason.to_string(),
+            Some(Ok(_)) => continue,
+            other => panic!("expected a close frame, got {:?}", other),
+        }
+    }
+}
+
+// just enough http to read a status line back
+async fn get(address: std::net::SocketAddr, path: &str, authorization: Option<&str>) -> String {
+    let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+    let authorization = authorization
+        .map(|token| format!("Authorization: Bearer {}\r\n", token))
+        .unwrap_or_default();
+    stream
+        .write_all(
+            format!(
+                "GET {} HTTP/1.1\r\nHost: {}\r\n{}Connection: close\r\n\r\n",
+                path, address, authorization
+            )
+            .as_bytes(),
+        )
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+#[tokio::test]
+async fn starts_on_ephemeral_ports_and_shuts_down() {
+    let relay = RelayServer::builder()
+        .mount("test")
+        .session_log(session_log("ephemeral"))
+        .start()
+        .await
+        .unwrap();
+    assert_ne!(relay.http_address().port(), 0);
+    assert_ne!(relay.listener_address().port(), 0);
+    assert_ne!(relay.streamer_address().port(), 0);
+    assert_eq!(relay.mounts(), vec!["test".to_string()]);
+
+    let stats = relay.stats();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].mount, "test");
+    assert_eq!(stats[0].listener_count, 0);
+    assert!(stats[0].streamer.is_none());
+    let health = relay.health();
+    assert!(!health.streamer_connected);
+    assert!(!health.pipeline_running);
+    assert_eq!(
+        relay.listeners("test").map(|listeners| listeners.len()),
+        Some(0)
+    );
+    assert!(relay.listeners("other").is_none());
+
+    tokio::time::timeout(Duration::from_secs(10), relay.shutdown())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn streamer_and_listener_show_up_in_stats() {
+    let relay = RelayServer::builder()
+        .session_log(session_log("on-air"))
+        .start()
+        .await
+        .unwrap();
+    let (mut streamer, _) = connect_async(format!("ws://{}", relay.streamer_address()))
+        .await
+        .unwrap();
+    streamer.send(Message::text("Test Title")).await.unwrap();
+    tokio::time::sleep(SETTLE).await;
+    let (listener, _) = connect_async(format!("ws://{}", relay.listener_address()))
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    let health = relay.health();
+    assert!(health.streamer_connected);
+    assert!(health.listener_bound);
+    let stats = relay.stats();
+    assert!(stats[0].streamer.is_some());
+    assert_eq!(stats[0].listener_count, 1);
+    assert_eq!(stats[0].now_playing.as_deref(), Some("Test Title"));
+    let listeners = relay.listeners(&stats[0].mount).unwrap();
+    assert_eq!(listeners[0].ip, "127.0.0.1");
+
+    let response = get(relay.http_address(), "/api/stats", None).await;
+    let (_, body) = response.split_once("\r\n\r\n").unwrap();
+    let public_stats: serde_json::Value = serde_json::from_str(body).unwrap();
+    let public_listener = &public_stats["mounts"][0]["listeners"][0];
+    assert!(public_listener["connected_since"].is_u64());
+    assert!(public_listener.get("ip").is_none());
+    assert!(public_listener.get("port").is_none());
+
+    drop(listener);
+    drop(streamer);
+    tokio::time::timeout(Duration::from_secs(10), relay.shutdown())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn pending_handshakes_count_against_the_per_ip_limit() {
+    let relay = RelayServer::builder()
+        .config(Config {
+            max_connections_per_ip: 1,
+            ..Config::default()
+        })
+        .session_log(session_log("per-ip"))
+        .start()
+        .await
+        .unwrap();
+    let (mut streamer, _) = connect_async(format!("ws://{}", relay.streamer_address()))
+        .await
+        .unwrap();
+    streamer.send(Message::text("Test Title")).await.unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    // holds its place without ever upgrading
+    let stalled = tokio::net::TcpStream::connect(relay.listener_address())
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+    let (mut listener, _) = connect_async(format!("ws://{}", relay.listener_address()))
+        .await
+        .unwrap();
+    match listener.next().await {
+        Some(Ok(Message::Close(Some(close_frame)))) => {
+            assert_eq!(close_frame.reason, "Connection Limit per IP Reached")
+        }
+        other => panic!("expected a rejection, got {:?}", other),
+    }
+    assert_eq!(relay.stats()[0].listener_count, 0);
+
+    drop(stalled);
+    drop(streamer);
+    tokio::time::timeout(Duration::from_secs(10), relay.shutdown())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn admin_api_needs_a_configured_token() {
+    let relay = RelayServer::builder()
+        .session_log(session_log("admin-off"))
+        .start()
+        .await
+        .unwrap();
+    let response = get(relay.http_address(), "/admin/listeners", Some("")).await;
+    assert!(response.starts_with("HTTP/1.1 404"), "{}", response);
+    relay.shutdown().await;
+
+    let relay = RelayServer::builder()
+        .admin_token("secret")
+        .session_log(session_log("admin-on"))
+        .start()
+        .await
+        .unwrap();
+    let response = get(relay.http_address(), "/admin/listeners", None).await;
+    assert!(response.starts_with("HTTP/1.1 401"), "{}", response);
+    let response = get(relay.http_address(), "/admin/listeners", Some("secreT")).await;
+    assert!(response.starts_with("HTTP/1.1 401"), "{}", response);
+    let response = get(relay.http_address(), "/admin/listeners", Some("secret")).await;
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    relay.shutdown().await;
+}
+
+#[tokio::test]
+async fn quality_beacons_are_capped() {
+    let relay = RelayServer::builder()
+        .session_log(session_log("quality"))
+        .start()
+        .await
+        .unwrap();
+    let (mut streamer, _) = connect_async(format!("ws://{}", relay.streamer_address()))
+        .await
+        .unwrap();
+    streamer.send(Message::text("Test Title")).await.unwrap();
+    tokio::time::sleep(SETTLE).await;
+    let mut listeners = vec![];
+    for _ in 0..2 {
+        let (mut listener, _) = connect_async(format!("ws://{}", relay.listener_address()))
+            .await
+            .unwrap();
+        listener
+            .send(Message::text(format!(
+                "{{\"type\":\"quality\",\"buffer_fill\":7.5,\"underruns\":{},\
+                 \"dropped_packets\":1,\"decode_errors\":0,\"sample_rate\":4000000000}}",
+                u64::MAX
+            )))
+            .await
+            .unwrap();
+        listeners.push(listener);
+    }
+    tokio::time::sleep(SETTLE).await;
+
+    let quality = relay.stats()[0].quality.clone();
+    assert_eq!(quality.reporting_listeners, 2);
+    assert_eq!(quality.average_buffer_fill, 1.0);
+    assert_eq!(quality.underruns, 2 * u32::MAX as u64);
+    assert_eq!(quality.dropped_packets, 2);
+    assert_eq!(quality.sample_rates.get(&768000), Some(&2));
+
+    drop(listeners);
+    drop(streamer);
+    tokio::time::timeout(Duration::from_secs(10), relay.shutdown())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn handover_needs_a_key_on_an_open_mount() {
+    let relay = RelayServer::builder()
+        .config(Config {
+            handover_policy: HandoverPolicy::Standby,
+            ..Config::default()
+        })
+        .session_log(session_log("open-standby"))
+        .start()
+        .await
+        .unwrap();
+    let (streamer, _) = connect_streamer(relay.streamer_address(), "").await;
+    let (mut waiting, _) = connect_streamer(relay.streamer_address(), "priority=9").await;
+    assert_eq!(close_reason(&mut waiting).await, "Stream Key Required");
+
+    drop(streamer);
+    tokio::time::timeout(Duration::from_secs(10), relay.shutdown())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn priority_comes_from_the_token() {
+    let relay = RelayServer::builder()
+        .config(Config {
+            handover_policy: HandoverPolicy::Priority,
+            streamer_tokens: vec![
+                StreamerToken {
+                    token: "low".to_string(),
+                    priority: 1,
+                },
+                StreamerToken {
+                    token: "high".to_string(),
+                    priority: 5,
+                },
+            ],
+            ..Config::default()
+        })
+        .session_log(session_log("priority"))
+        .start()
+        .await
+        .unwrap();
+    let (mut on_air, on_air_port) = connect_streamer(relay.streamer_address(), "token=low").await;
+    let (claiming, _) = connect_streamer(relay.streamer_address(), "token=low&priority=200").await;
+    assert_eq!(
+        relay.stats()[0].streamer.as_ref().unwrap().port,
+        on_air_port
+    );
+
+    let (taking_over, taking_over_port) =
+        connect_streamer(relay.streamer_address(), "token=high").await;
+    tokio::time::sleep(SETTLE).await;
+    assert_eq!(
+        relay.stats()[0].streamer.as_ref().unwrap().port,
+        taking_over_port
+    );
+    // the one pushed off air is hung up on
+    while let Some(Ok(message)) = on_air.next().await {
+        if message.is_close() {
+            break;
+        }
+    }
+
+    drop((claiming, taking_over));
+    tokio::time::timeout(Duration::from_secs(10), relay.shutdown())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn oversized_listener_messages_drop_the_listener() {
+    let relay = RelayServer::builder()
+        .session_log(session_log("oversized"))
+        .start()
+        .await
+        .unwrap();
+    let (mut streamer, _) = connect_async(format!("ws://{}", relay.streamer_address()))
+        .await
+        .unwrap();
+    streamer.send(Message::text("Test Title")).await.unwrap();
+    tokio::time::sleep(SETTLE).await;
+    let (mut listener, _) = connect_async(format!("ws://{}", relay.listener_address()))
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+    assert_eq!(relay.stats()[0].listener_count, 1);
+
+    let text = "a".repeat(64 * 1024);
+    let _ = listener
+        .send(Message::text(format!(
+            "{{\"type\":\"chat\",\"text\":\"{}\"}}",
+            text
+        )))
+        .await;
+    tokio::time::sleep(SETTLE).await;
+    assert_eq!(relay.stats()[0].listener_count, 0);
+
+    drop(listener);
+    drop(streamer);
+    tokio::time::timeout(Duration::from_secs(10), relay.shutdown())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn silent_streamer_does_not_hold_up_shutdown() {
+    let relay = RelayServer::builder()
+        .session_log(session_log("silent-streamer"))
+        .start()
+        .await
+        .unwrap();
+    let silent = tokio::net::TcpStream::connect(relay.streamer_address())
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    tokio::time::timeout(Duration::from_secs(2), relay.shutdown())
+        .await
+        .unwrap();
+    drop(silent);
+}
+
+#[tokio::test]
+async fn late_handshake_does_not_outlive_the_broadcast() {
+    let relay = RelayServer::builder()
+        .config(Config {
+            handover_policy: HandoverPolicy::Standby,
+            streamer_tokens: vec![StreamerToken {
+                token: "key".to_string(),
+                priority: 1,
+            }],
+            ..Config::default()
+        })
+        .session_log(session_log("late-handshake"))
+        .start()
+        .await
+        .unwrap();
+    let (on_air, _) = connect_streamer(relay.streamer_address(), "token=key").await;
+    // taken in while the mount is busy, upgraded only once it is not
+    let late = tokio::net::TcpStream::connect(relay.streamer_address())
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+    drop(on_air);
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert!(relay.stats()[0].streamer.is_none());
+
+    let (mut late, _) = tokio_tungstenite::client_async(
+        format!("ws://{}/?token=key", relay.streamer_address()),
+        tokio_tungstenite::MaybeTlsStream::Plain(late),
+    )
+    .await
+    .unwrap();
+    let reason = tokio::time::timeout(Duration::from_secs(5), close_reason(&mut late))
+        .await
+        .unwrap();
+    assert_eq!(reason, "Mount Off Air");
+
+    tokio::time::timeout(Duration::from_secs(10), relay.shutdown())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn session_reports_only_cover_recent_sessions() {
+    let path = session_log("recent-sessions");
+    let persisted_session = "{\"mount\":\"radioxide\",\"show_started\":1,\"joined\":1,\"left\":2,\
+         \"duration\":1,\"bytes_sent\":0,\"reason\":\"Left\",\"user_agent\":null}\n";
+    std::fs::write(&path, persisted_session.repeat(10005)).unwrap();
+    let relay = RelayServer::builder()
+        .session_log(path.clone())
+        .start()
+        .await
+        .unwrap();
+
+    let response = get(relay.http_address(), "/api/sessions", None).await;
+    let (_, body) = response.split_once("\r\n\r\n").unwrap();
+    let session_report: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(session_report["session_count"], 10000);
+
+    tokio::time::timeout(Duration::from_secs(10), relay.shutdown())
+        .await
+        .unwrap();
+    let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,104 @@
+use back::schedule::{minute_of_week, parse_clock, Schedule, MINUTES_PER_DAY};
+
+// 1970-01-05 was the first monday after the epoch
+const MONDAY: u64 = 4 * 86400;
+const HOUR: u64 = 3600;
+
+const SCHEDULE: &str = "
+# name | mount | weekday | window | stream key
+Morning | radio | mon | 08:00-10:00 | morning-key
+Late | radio | mon | 23:00-01:00 | late-key
+Other Mount | other | mon | 08:00-10:00 | other-key
+Broken | radio | mon | 25:00-26:00 | broken-key
+Missing Key | radio | tue | 08:00-10:00 |
+Not A Show
+";
+
+#[test]
+fn minute_of_week_starts_on_monday() {
+    assert_eq!(minute_of_week(MONDAY), 0);
+    assert_eq!(minute_of_week(MONDAY + 90), 1);
+    assert_eq!(minute_of_week(0), 3 * MINUTES_PER_DAY);
+    assert_eq!(
+        minute_of_week(MONDAY + 7 * 86400 - 60),
+        7 * MINUTES_PER_DAY - 1
+    );
+    assert_eq!(minute_of_week(MONDAY + 7 * 86400), 0);
+}
+
+#[test]
+fn parse_clock_rejects_impossible_times() {
+    assert_eq!(parse_clock("08:30"), Some(510));
+    assert_eq!(parse_clock(" 23:59 "), Some(1439));
+    assert_eq!(parse_clock("24:00"), None);
+    assert_eq!(parse_clock("12:60"), None);
+    assert_eq!(parse_clock("noon"), None);
+}
+
+#[test]
+fn parse_skips_invalid_lines() {
+    let schedule = Schedule::parse(SCHEDULE);
+    let names: Vec<String> = schedule
+        .slots(MONDAY)
+        .into_iter()
+        .map(|slot| slot.name)
+        .collect();
+    assert_eq!(names, ["Morning", "Other Mount", "Late"]);
+    assert!(schedule.has_mount("radio"));
+    assert!(!schedule.has_mount("missing"));
+}
+
+#[test]
+fn on_air_follows_the_mount_and_the_clock() {
+    let schedule = Schedule::parse(SCHEDULE);
+    assert!(schedule.on_air("radio", MONDAY + 7 * HOUR).is_none());
+    let morning = schedule.on_air("radio", MONDAY + 8 * HOUR).unwrap();
+    assert_eq!(morning.name, "Morning");
+    assert!(morning.is_allowed(Some("morning-key")));
+    assert!(!morning.is_allowed(Some("other-key")));
+    assert!(!morning.is_allowed(None));
+    assert_eq!(
+        schedule.on_air("other", MONDAY + 9 * HOUR).unwrap().name,
+        "Other Mount"
+    );
+    assert!(schedule.on_air("radio", MONDAY + 10 * HOUR).is_none());
+    // runs past midnight into tuesday
+    assert_eq!(
+        schedule
+            .on_air("radio", MONDAY + 24 * HOUR + 30 * 60)
+            .unwrap()
+            .name,
+        "Late"
+    );
+}
+
+#[test]
+fn remaining_counts_down_to_the_slot_end() {
+    let schedule = Schedule::parse(SCHEDULE);
+    let morning = schedule.on_air("radio", MONDAY + 8 * HOUR).unwrap();
+    assert_eq!(morning.remaining(MONDAY + 8 * HOUR), 2 * HOUR);
+    assert_eq!(morning.remaining(MONDAY + 9 * HOUR + 59 * 60 + 30), 30);
+    assert_eq!(morning.remaining(MONDAY + 10 * HOUR), 0);
+    let late = schedule.on_air("radio", MONDAY + 23 * HOUR).unwrap();
+    assert_eq!(late.remaining(MONDAY + 24 * HOUR), HOUR);
+}
+
+#[test]
+fn up_next_only_within_the_window() {
+    let schedule = Schedule::parse(SCHEDULE);
+    let before_morning = MONDAY + 8 * HOUR - 5 * 60;
+    let morning = schedule.up_next("radio", before_morning, 600).unwrap();
+    assert_eq!(morning.name, "Morning");
+    assert_eq!(morning.starts_in(before_morning), 5 * 60);
+    assert!(schedule.up_next("radio", before_morning, 60).is_none());
+    // the slot on air is not the next one
+    let during_morning = MONDAY + 9 * HOUR;
+    assert!(schedule.up_next("radio", during_morning, 600).is_none());
+    assert_eq!(
+        schedule
+            .up_next("radio", during_morning, 14 * HOUR)
+            .unwrap()
+            .name,
+        "Late"
+    );
+}
@@ -0,0 +1,225 @@
+use std::{
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use brotli::CompressorWriter;
+use dioxus::{
+    prelude::spawn,
+    signals::{Signal, Writable},
+};
+use futures_util::StreamExt;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    AudioContext, AudioContextOptions, AudioProcessingEvent, MediaStream, MediaStreamConstraints,
+    MediaStreamTrack,
+};
+
+use crate::{
+    listener_address,
+    messaging::{socket_writer, Outbox, RelayMessage},
+};
+
+// what the relay mixes callers in as, the same layout the streamer sends
+const CALL_SAMPLE_RATE: f32 = 48000.0;
+const CALL_CHANNELS: usize = 2;
+const CALL_PRECISION: usize = 4;
+const CAPTURE_BUFFER_LENGTH: u32 = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CallState {
+    Idle,
+    Waiting,
+    OnAir,
+}
+
+// the microphone side, torn down when the call ends
+struct Capture {
+    context: AudioContext,
+    media_stream: MediaStream,
+    _on_audio_process: Closure<dyn FnMut(AudioProcessingEvent)>,
+}
+
+pub async fn start_calling(
+    mut call_state: Signal<CallState>,
+    mut call_notice: Signal<Option<String>>,
+    mut call_outbox: Signal<Option<Outbox>>,
+    token: String,
+    name: String,
+) {
+    let connect_addr = format!(
+        "{}/call?token={}&name={}",
+        listener_address(),
+        String::from(js_sys::encode_uri_component(&token)),
+        String::from(js_sys::encode_uri_component(&name))
+    );
+    let ws_stream = match tokio_tungstenite_wasm::connect(connect_addr.as_str()).await {
+        Ok(ws_stream) => ws_stream,
+        Err(err_val) => {
+            log::error!("Error: Call | {}", err_val);
+            call_notice.set(Some("Relay Unreachable".to_string()));
+            return;
+        }
+    };
+    let (ws_sink, mut ws_stream) = ws_stream.split();
+    let (outbox, outbox_receiver) = futures_channel::mpsc::unbounded();
+    call_outbox.set(Some(outbox.clone()));
+    spawn(async move {
+        socket_writer(outbox_receiver, ws_sink).await;
+    });
+    // frames are only sent once the streamer lets the caller on, the relay drops them before
+    let on_air = Arc::new(AtomicBool::new(false));
+    let capture = match start_capture(outbox, on_air.clone()).await {
+        Ok(capture) => capture,
+        Err(err_val) => {
+            log::error!("Error: Microphone | {:?}", err_val);
+            call_notice.set(Some("Microphone Unavailable".to_string()));
+            call_outbox.set(None);
+            return;
+        }
+    };
+    call_state.set(CallState::Waiting);
+    call_notice.set(None);
+
+    while let Some(message) = ws_stream.next().await {
+        let message = match message {
+            Ok(message) => message,
+            Err(err_val) => {
+                log::error!("Error: Call | {}", err_val);
+                break;
+            }
+        };
+        match message {
+            tokio_tungstenite_wasm::Message::Text(text) => {
+                match serde_json::from_str::<RelayMessage>(&text) {
+                    Ok(RelayMessage::CallStatus {
+                        on_air: is_on_air, ..
+                    }) => {
+                        on_air.store(is_on_air, Ordering::Relaxed);
+                        call_state.set(match is_on_air {
+                            true => CallState::OnAir,
+                            false => CallState::Waiting,
+                        });
+                    }
+                    Ok(RelayMessage::Error { reason }) => call_notice.set(Some(reason)),
+                    Ok(_) => {}
+                    Err(err_val) => log::warn!("Warning: Relay Message | {}", err_val),
+                }
+            }
+            tokio_tungstenite_wasm::Message::Close(close_frame) => {
+                if let Some(close_frame) = close_frame {
+                    call_notice.set(Some(close_frame.reason.to_string()));
+                }
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    stop_capture(capture).await;
+    call_outbox.set(None);
+    call_state.set(CallState::Idle);
+    log::info!("Call Ended");
+}
+
+async fn start_capture(outbox: Outbox, on_air: Arc<AtomicBool>) -> Result<Capture, JsValue> {
+    let window = web_sys::window().ok_or("No Window")?;
+    let mut constraints = MediaStreamConstraints::new();
+    constraints.audio(&JsValue::TRUE);
+    let media_stream: MediaStream = JsFuture::from(
+        window
+            .navigator()
+            .media_devices()?
+            .get_user_media_with_constraints(&constraints)?,
+    )
+    .await?
+    .dyn_into()?;
+
+    let mut context_options = AudioContextOptions::new();
+    context_options.sample_rate(CALL_SAMPLE_RATE);
+    let context = AudioContext::new_with_context_options(&context_options)?;
+    let source = context.create_media_stream_source(&media_stream)?;
+    let processor = context
+        .create_script_processor_with_buffer_size_and_number_of_input_channels_and_number_of_output_channels(
+            CAPTURE_BUFFER_LENGTH,
+            1,
+            1,
+        )?;
+    let on_audio_process =
+        Closure::<dyn FnMut(AudioProcessingEvent)>::new(move |event: AudioProcessingEvent| {
+            if !on_air.load(Ordering::Relaxed) {
+                return;
+            }
+            let samples = match event
+                .input_buffer()
+                .and_then(|input_buffer| input_buffer.get_channel_data(0))
+            {
+                Ok(samples) => samples,
+                Err(err_val) => {
+                    log::error!("Error: Microphone | {:?}", err_val);
+                    return;
+                }
+            };
+            let frame = call_frame(&samples);
+            if let Err(err_val) =
+                outbox.unbounded_send(tokio_tungstenite_wasm::Message::Binary(frame))
+            {
+                log::error!("Error: Call | {}", err_val);
+            }
+        });
+    processor.set_onaudioprocess(Some(on_audio_process.as_ref().unchecked_ref()));
+    source.connect_with_audio_node(&processor)?;
+    // a processor only runs while something pulls on it, its output is left silent
+    processor.connect_with_audio_node(&context.destination())?;
+    Ok(Capture {
+        context,
+        media_stream,
+        _on_audio_process: on_audio_process,
+    })
+}
+
+async fn stop_capture(capture: Capture) {
+    for track in capture.media_stream.get_tracks().iter() {
+        if let Ok(track) = track.dyn_into::<MediaStreamTrack>() {
+            track.stop();
+        }
+    }
+    match capture.context.close() {
+        Ok(closing) => {
+            if let Err(err_val) = JsFuture::from(closing).await {
+                log::error!("Error: Microphone | {:?}", err_val);
+            }
+        }
+        Err(err_val) => log::error!("Error: Microphone | {:?}", err_val),
+    }
+}
+
+// the microphone is mono, every sample goes out once per stream channel as signed "+1234" text
+fn call_frame(samples: &[f32]) -> Vec<u8> {
+    let largest = 1.0 - 10_f32.powi(-(CALL_PRECISION as i32));
+    let mut data = String::with_capacity(samples.len() * CALL_CHANNELS * (CALL_PRECISION + 1));
+    for &sample in samples {
+        let sample = match sample.is_nan() {
+            true => 0.0,
+            false => sample.clamp(-largest, largest),
+        };
+        let formatted = format!("{:+.*}", CALL_PRECISION, sample);
+        let (sign, magnitude) = formatted.split_at(1);
+        let fraction = magnitude
+            .split_once('.')
+            .map_or("", |(_, fraction)| fraction);
+        for _ in 0..CALL_CHANNELS {
+            data.push_str(sign);
+            data.push_str(fraction);
+        }
+    }
+    let mut compression_writer = CompressorWriter::new(vec![], 4096, 4, 24);
+    if let Err(err_val) = compression_writer.write_all(data.as_bytes()) {
+        log::error!("Error: Compression | {}", err_val);
+    }
+    compression_writer.into_inner()
+}
@@ -1,5 +1,10 @@
 use crate::{
-    status::{coin_status_check, server_status_check, Coin, CoinStatus, Server, ServerStatus},
+    calling::{start_calling, CallState},
+    messaging::{use_messaging, ChatLine, ListenerRequest, Messaging, SongRequest},
+    status::{
+        coin_status_check, schedule_check, server_status_check, Coin, CoinStatus, Schedule, Server,
+        ServerStatus, Show,
+    },
     streaming::start_listening,
 };
 use dioxus::prelude::*;
@@ -9,6 +14,7 @@ use std::time::Duration;
 pub fn listen_renderer() -> Element {
     let mut is_listening = use_signal(|| false);
     let is_maintaining = use_signal(|| (false, false));
+    let messaging = use_messaging();
     let call_start_listening = move |_| {
         if !is_listening() {
             if !is_maintaining().0 && !is_maintaining().1 {
@@ -17,7 +23,7 @@ pub fn listen_renderer() -> Element {
                     to_owned![is_maintaining];
                     is_listening.set(true);
                     async move {
-                        start_listening(is_maintaining, is_listening).await;
+                        start_listening(is_maintaining, is_listening, messaging).await;
                     }
                 });
             }
@@ -43,6 +49,55 @@ pub fn listen_renderer() -> Element {
                     }
                 }
             }
+            chat_renderer { messaging: messaging }
+            song_request_renderer { messaging: messaging }
+        }
+    }
+}
+
+#[component]
+pub fn chat_renderer(messaging: Messaging) -> Element {
+    let mut nick_draft = use_signal(String::new);
+    let mut chat_draft = use_signal(String::new);
+    let send_nick = move |_| {
+        messaging.send(ListenerRequest::Nick { nick: nick_draft() });
+    };
+    let send_chat = move |_| {
+        messaging.send(ListenerRequest::Chat { text: chat_draft() });
+        chat_draft.set(String::new());
+    };
+    rsx! {
+        div {
+            span { "Chat" }
+            for chat_line in (messaging.chat)() {
+                ShowChatLine { chat_line: chat_line }
+            }
+            if let Some(notice) = (messaging.notice)() {
+                div { "{notice}" }
+            }
+            if messaging.is_connected() {
+                div { class: "flex items-center",
+                    input {
+                        placeholder: "Nick",
+                        value: "{nick_draft}",
+                        oninput: move |event| nick_draft.set(event.value()),
+                    }
+                    button { onclick: send_nick,
+                        if (messaging.nick)().is_some() { "Change Nick" } else { "Set Nick" }
+                    }
+                }
+                if let Some(nick) = (messaging.nick)() {
+                    div { class: "flex items-center",
+                        span { "{nick}: " }
+                        input {
+                            placeholder: "Say something",
+                            value: "{chat_draft}",
+                            oninput: move |event| chat_draft.set(event.value()),
+                        }
+                        button { onclick: send_chat, "Send" }
+                    }
+                }
+            }
         }
     }
 }
@@ -138,6 +193,153 @@ pub fn coin_status_renderer(server_address: String) -> Element {
     }
 }
 #[component]
+pub fn schedule_renderer(server_address: String) -> Element {
+    let schedule_check_time = 60_u64;
+    let mut schedule = use_signal(move || Schedule { shows: vec![] });
+    let _schedule_task: Coroutine<()> = use_coroutine(|_| async move {
+        loop {
+            if let Some(schedule_reported) = schedule_check(&server_address).await {
+                *schedule.write() = schedule_reported;
+            }
+            tokio_with_wasm::tokio::time::sleep(Duration::from_secs(schedule_check_time)).await;
+        }
+    });
+    rsx! {
+        div {
+            span { "Schedule (UTC)" }
+            for show in schedule().shows {
+                ShowScheduleSlot { show: show }
+            }
+        }
+    }
+}
+#[component]
+pub fn call_renderer() -> Element {
+    let call_state = use_signal(|| CallState::Idle);
+    let call_notice = use_signal(|| None::<String>);
+    let mut call_outbox = use_signal(|| None);
+    let mut token_draft = use_signal(String::new);
+    let mut name_draft = use_signal(String::new);
+    let call_in = move |_| {
+        if call_outbox.read().is_none() {
+            spawn(async move {
+                start_calling(
+                    call_state,
+                    call_notice,
+                    call_outbox,
+                    token_draft(),
+                    name_draft(),
+                )
+                .await;
+            });
+        }
+    };
+    let hang_up = move |_| {
+        if let Some(outbox) = call_outbox.write().take() {
+            let _ = outbox.unbounded_send(tokio_tungstenite_wasm::Message::Close(None));
+        }
+    };
+    rsx! {
+        div {
+            span { "Call In" }
+            if let Some(notice) = call_notice() {
+                div { "{notice}" }
+            }
+            {
+                match call_state() {
+                    CallState::Idle => rsx! {
+                        div { class: "flex items-center",
+                            input {
+                                placeholder: "Call Token",
+                                value: "{token_draft}",
+                                oninput: move |event| token_draft.set(event.value()),
+                            }
+                            input {
+                                placeholder: "Name",
+                                value: "{name_draft}",
+                                oninput: move |event| name_draft.set(event.value()),
+                            }
+                            button { onclick: call_in, "Call" }
+                        }
+                    },
+                    CallState::Waiting => rsx! {
+                        div { class: "flex items-center",
+                            span { "Waiting for the host" }
+                            button { onclick: hang_up, "Hang Up" }
+                        }
+                    },
+                    CallState::OnAir => rsx! {
+                        div { class: "flex items-center",
+                            span { "On Air" }
+                            button { onclick: hang_up, "Hang Up" }
+                        }
+                    },
+                }
+            }
+        }
+    }
+}
+#[component]
+pub fn song_request_renderer(messaging: Messaging) -> Element {
+    let mut song_draft = use_signal(String::new);
+    let send_song_request = move |_| {
+        messaging.send(ListenerRequest::SongRequest { song: song_draft() });
+        song_draft.set(String::new());
+    };
+    rsx! {
+        div {
+            span { "Song Requests" }
+            for song_request in (messaging.song_requests)() {
+                ShowSongRequest { song_request: song_request }
+            }
+            if messaging.is_connected() {
+                div { class: "flex items-center",
+                    input {
+                        placeholder: "Song",
+                        value: "{song_draft}",
+                        oninput: move |event| song_draft.set(event.value()),
+                    }
+                    button { onclick: send_song_request, "Request" }
+                    button {
+                        onclick: move |_| messaging.send(ListenerRequest::Library),
+                        "Library"
+                    }
+                }
+                for song in (messaging.library)() {
+                    div { class: "flex items-center",
+                        span { "{song}" }
+                        button {
+                            onclick: {
+                                let song = song.clone();
+                                move |_| messaging.send(ListenerRequest::SongRequest { song: song.clone() })
+                            },
+                            "Request"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+#[component]
+fn ShowSongRequest(song_request: SongRequest) -> Element {
+    rsx! {
+        div {
+            span { "{song_request.song}: " }
+            span { "{song_request.status:?}" }
+        }
+    }
+}
+#[component]
+fn ShowChatLine(chat_line: ChatLine) -> Element {
+    rsx! {
+        div {
+            span { "{chat_line.nick}: " }
+            span { { chat_line.text } }
+        }
+    }
+}
+#[component]
 fn ShowServerStatus(server_status: ServerStatus) -> Element {
     rsx! {
         div {
@@ -159,3 +361,17 @@ fn ShowCoinStatus(coin_status: CoinStatus) -> Element {
         }
     }
 }
+#[component]
+fn ShowScheduleSlot(show: Show) -> Element {
+    rsx! {
+        div {
+            div { class: "flex items-center",
+            span { "{show.weekday} {show.start}-{show.end}: " }
+            span { { show.name } }
+            if show.on_air {
+                span { " (On Air)" }
+            }
+            }
+        }
+    }
+}
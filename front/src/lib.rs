@@ -1,7 +1,31 @@
+pub mod calling;
 pub mod components;
 pub mod listening;
+pub mod messaging;
+pub mod quality;
 pub mod status;
 pub mod streaming;
 
 static BUFFER_LENGTH: usize = 1000000;
 static BUFFER_LIMIT: usize = BUFFER_LENGTH / 100 * 90;
+static CHAT_LOG_LENGTH: usize = 100;
+static SONG_REQUEST_LOG_LENGTH: usize = 20;
+static DEFAULT_RELAY_ADDRESS: &str = "http://192.168.1.2:2323";
+static DEFAULT_LISTENER_ADDRESS: &str = "ws://192.168.1.2:2424";
+
+// a relay serving this page writes its own addresses into it, a page hosted elsewhere falls back
+pub fn relay_address() -> String {
+    relay_meta("radioxide-relay").unwrap_or_else(|| DEFAULT_RELAY_ADDRESS.to_string())
+}
+
+pub fn listener_address() -> String {
+    relay_meta("radioxide-listener").unwrap_or_else(|| DEFAULT_LISTENER_ADDRESS.to_string())
+}
+
+fn relay_meta(name: &str) -> Option<String> {
+    web_sys::window()?
+        .document()?
+        .query_selector(&format!("meta[name=\"{}\"]", name))
+        .ok()??
+        .get_attribute("content")
+}
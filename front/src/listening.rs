@@ -1,25 +1,43 @@
-use std::{mem::MaybeUninit, sync::Arc, time::Duration};
+use std::{
+    mem::MaybeUninit,
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
 
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use dioxus::signals::Signal;
 use ringbuf::{Consumer, SharedRb};
 
-use crate::BUFFER_LIMIT;
+use crate::{quality::QualityCounters, BUFFER_LIMIT};
 
 pub async fn listen_podcast(
     is_listening: Signal<bool>,
     mut consumer: Consumer<f32, Arc<SharedRb<f32, Vec<MaybeUninit<f32>>>>>,
+    quality: Arc<QualityCounters>,
 ) {
     log::info!("Attention! Show must start!");
     let host = cpal::default_host();
     let output_device = host.default_output_device().unwrap();
     let config: cpal::StreamConfig = output_device.default_output_config().unwrap().into();
+    quality
+        .sample_rate
+        .store(config.sample_rate.0, Ordering::Relaxed);
 
+    // silence before the first packet lands is just the stream starting, not an underrun
+    let mut is_playing = false;
     let output_data_fn = move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
         if consumer.len() > BUFFER_LIMIT {
+            quality.flushed(consumer.len());
             consumer.clear();
             log::error!("Slow Consumer: DROPPED ALL Packets");
         }
+        if consumer.len() < data.len() {
+            if is_playing {
+                quality.underruns.fetch_add(1, Ordering::Relaxed);
+            }
+        } else {
+            is_playing = true;
+        }
         for sample in data {
             *sample = match consumer.pop() {
                 Some(s) => s,
@@ -1,5 +1,8 @@
 use dioxus::prelude::*;
-use front::components::listen_renderer;
+use front::{
+    components::{call_renderer, listen_renderer, schedule_renderer},
+    relay_address,
+};
 
 fn main() {
     println!("Hello, world!");
@@ -11,8 +14,10 @@ fn app() -> Element {
     rsx! {
         page_base {}
         listen_renderer {}
-        // coin_status_renderer {server_address:server_address.clone()}
-        // server_status_renderer {server_address:server_address.clone()}
+        call_renderer {}
+        schedule_renderer { server_address: relay_address() }
+        // coin_status_renderer {server_address:relay_address()}
+        // server_status_renderer {server_address:relay_address()}
     }
 }
 
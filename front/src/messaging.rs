@@ -0,0 +1,178 @@
+use dioxus::{
+    hooks::use_signal,
+    signals::{Readable, Signal, Writable},
+};
+use futures_channel::mpsc::{UnboundedReceiver, UnboundedSender};
+use futures_util::{stream::SplitSink, SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{CHAT_LOG_LENGTH, SONG_REQUEST_LOG_LENGTH};
+
+// the socket has one writer, everything else hands it frames through here
+pub type Outbox = UnboundedSender<tokio_tungstenite_wasm::Message>;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ListenerRequest {
+    Nick { nick: String },
+    Chat { text: String },
+    SongRequest { song: String },
+    Library,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RelayMessage {
+    Nick {
+        nick: String,
+    },
+    Chat {
+        nick: String,
+        text: String,
+        timestamp: u64,
+    },
+    Error {
+        reason: String,
+    },
+    SongRequestStatus {
+        id: u64,
+        song: String,
+        status: SongRequestStatus,
+    },
+    Library {
+        songs: Vec<String>,
+    },
+    CallStatus {
+        id: u64,
+        on_air: bool,
+    },
+    // streamer side messages share the socket, a listener has nothing to do with them
+    #[serde(other)]
+    Unhandled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SongRequestStatus {
+    Pending,
+    Accepted,
+    Rejected,
+    Queued,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SongRequest {
+    pub id: u64,
+    pub song: String,
+    pub status: SongRequestStatus,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatLine {
+    pub nick: String,
+    pub text: String,
+}
+
+// what the text side of the socket has told us, kept while the listener reconnects
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Messaging {
+    pub outbox: Signal<Option<Outbox>>,
+    pub nick: Signal<Option<String>>,
+    pub chat: Signal<Vec<ChatLine>>,
+    pub notice: Signal<Option<String>>,
+    pub song_requests: Signal<Vec<SongRequest>>,
+    pub library: Signal<Vec<String>>,
+}
+
+pub fn use_messaging() -> Messaging {
+    Messaging {
+        outbox: use_signal(|| None),
+        nick: use_signal(|| None),
+        chat: use_signal(Vec::new),
+        notice: use_signal(|| None),
+        song_requests: use_signal(Vec::new),
+        library: use_signal(Vec::new),
+    }
+}
+
+impl Messaging {
+    pub fn is_connected(&self) -> bool {
+        self.outbox.read().is_some()
+    }
+
+    pub fn send(&self, request: ListenerRequest) {
+        let Some(outbox) = (self.outbox)() else {
+            return;
+        };
+        match serde_json::to_string(&request) {
+            Ok(request) => {
+                if let Err(err_val) =
+                    outbox.unbounded_send(tokio_tungstenite_wasm::Message::Text(request))
+                {
+                    log::error!("Error: Listener Request | {}", err_val);
+                }
+            }
+            Err(err_val) => log::error!("Error: Listener Request | {}", err_val),
+        }
+    }
+
+    pub fn receive(&mut self, text: &str) {
+        let relay_message = match serde_json::from_str(text) {
+            Ok(relay_message) => relay_message,
+            Err(err_val) => {
+                log::warn!("Warning: Relay Message | {}", err_val);
+                return;
+            }
+        };
+        match relay_message {
+            RelayMessage::Nick { nick } => {
+                self.nick.set(Some(nick));
+                self.notice.set(None);
+            }
+            RelayMessage::Chat { nick, text, .. } => {
+                let mut chat = self.chat.write();
+                chat.push(ChatLine { nick, text });
+                if chat.len() > CHAT_LOG_LENGTH {
+                    let overflow = chat.len() - CHAT_LOG_LENGTH;
+                    chat.drain(..overflow);
+                }
+            }
+            RelayMessage::Error { reason } => self.notice.set(Some(reason)),
+            // every decision is announced to everyone, only the ones asked from here are kept
+            RelayMessage::SongRequestStatus { id, song, status } => {
+                let mut song_requests = self.song_requests.write();
+                match song_requests
+                    .iter_mut()
+                    .find(|song_request| song_request.id == id)
+                {
+                    Some(song_request) => song_request.status = status,
+                    None if status == SongRequestStatus::Pending => {
+                        song_requests.push(SongRequest { id, song, status });
+                        if song_requests.len() > SONG_REQUEST_LOG_LENGTH {
+                            song_requests.remove(0);
+                        }
+                    }
+                    None => {}
+                }
+            }
+            RelayMessage::Library { songs } => self.library.set(songs),
+            RelayMessage::CallStatus { .. } | RelayMessage::Unhandled => {}
+        }
+    }
+}
+
+pub async fn socket_writer(
+    mut outbox_receiver: UnboundedReceiver<tokio_tungstenite_wasm::Message>,
+    mut ws_sink: SplitSink<
+        tokio_tungstenite_wasm::WebSocketStream,
+        tokio_tungstenite_wasm::Message,
+    >,
+) {
+    while let Some(message) = outbox_receiver.next().await {
+        if let Err(err_val) = ws_sink.send(message).await {
+            log::error!("Error: Socket Writer | {}", err_val);
+            break;
+        }
+    }
+    let _ = ws_sink.close().await;
+}
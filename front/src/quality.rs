@@ -0,0 +1,81 @@
+use std::{
+    sync::{
+        atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use dioxus::signals::Signal;
+use serde::Serialize;
+
+use crate::{messaging::Outbox, BUFFER_LENGTH};
+
+const BEACON_INTERVAL: u64 = 10;
+
+// shared by the socket, the sound card callback and the beacon, counters run from connecting
+#[derive(Debug, Default)]
+pub struct QualityCounters {
+    pub buffered: AtomicUsize,
+    pub packet_length: AtomicUsize,
+    pub underruns: AtomicU64,
+    pub dropped_packets: AtomicU64,
+    pub decode_errors: AtomicU64,
+    pub sample_rate: AtomicU32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename = "quality")]
+struct QualityBeacon {
+    buffer_fill: f32,
+    underruns: u64,
+    dropped_packets: u64,
+    decode_errors: u64,
+    sample_rate: u32,
+}
+
+impl QualityCounters {
+    // a flushed buffer counts as however many packets it was holding
+    pub fn flushed(&self, samples: usize) {
+        let packet_length = self.packet_length.load(Ordering::Relaxed).max(1);
+        self.dropped_packets
+            .fetch_add(samples.div_ceil(packet_length) as u64, Ordering::Relaxed);
+    }
+
+    fn beacon(&self) -> QualityBeacon {
+        QualityBeacon {
+            buffer_fill: self.buffered.load(Ordering::Relaxed) as f32 / BUFFER_LENGTH as f32,
+            underruns: self.underruns.load(Ordering::Relaxed),
+            dropped_packets: self.dropped_packets.load(Ordering::Relaxed),
+            decode_errors: self.decode_errors.load(Ordering::Relaxed),
+            sample_rate: self.sample_rate.load(Ordering::Relaxed),
+        }
+    }
+}
+
+pub async fn quality_beacon(
+    is_listening: Signal<bool>,
+    quality: Arc<QualityCounters>,
+    outbox: Outbox,
+) {
+    // wakes every second so the socket is let go soon after the listener stops
+    let mut seconds = 0;
+    while is_listening() {
+        tokio_with_wasm::tokio::time::sleep(Duration::from_secs(1)).await;
+        seconds += 1;
+        if seconds % BEACON_INTERVAL != 0 || !is_listening() {
+            continue;
+        }
+        let beacon = match serde_json::to_string(&quality.beacon()) {
+            Ok(beacon) => beacon,
+            Err(err_val) => {
+                log::error!("Error: Quality Beacon | {}", err_val);
+                continue;
+            }
+        };
+        if let Err(err_val) = outbox.unbounded_send(tokio_tungstenite_wasm::Message::Text(beacon)) {
+            log::error!("Error: Quality Beacon | {}", err_val);
+            break;
+        }
+    }
+}
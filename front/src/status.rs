@@ -39,6 +39,19 @@ pub struct ServerStatus {
 pub struct CoinStatus {
     pub status: Coin,
 }
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Show {
+    pub name: String,
+    pub mount: String,
+    pub weekday: String,
+    pub start: String,
+    pub end: String,
+    pub on_air: bool,
+}
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Schedule {
+    pub shows: Vec<Show>,
+}
 
 pub async fn server_status_check(
     mut server_status: Signal<ServerStatus>,
@@ -46,13 +59,9 @@ pub async fn server_status_check(
 ) -> ServerStatus {
     match reqwest::get(server_address).await {
         Ok(response) => match response.json::<ServerStatus>().await {
-            Ok(_) => {
-                *server_status.write() = ServerStatus {
-                    status: Server::Alive,
-                };
-                ServerStatus {
-                    status: Server::Alive,
-                }
+            Ok(server_status_reported) => {
+                *server_status.write() = server_status_reported.clone();
+                server_status_reported
             }
             Err(err_val) => {
                 *server_status.write() = ServerStatus {
@@ -90,3 +99,18 @@ pub async fn coin_status_check(server_address: &String) -> Option<CoinStatus> {
         }
     }
 }
+pub async fn schedule_check(server_address: &String) -> Option<Schedule> {
+    match reqwest::get(format!("{}{}", server_address, "/api/schedule")).await {
+        Ok(response) => match response.json::<Schedule>().await {
+            Ok(schedule) => Some(schedule),
+            Err(err_val) => {
+                log::error!("Error: Can't Deserialise -> {}", err_val);
+                None
+            }
+        },
+        Err(err_val) => {
+            log::error!("Error: Response from Server -> {}", err_val);
+            None
+        }
+    }
+}
@@ -3,22 +3,33 @@ use dioxus::{
     prelude::spawn,
     signals::{Signal, Writable},
 };
-use futures_util::StreamExt;
+use futures_util::{stream::SplitStream, StreamExt};
 use ringbuf::{HeapRb, Producer, SharedRb};
-use std::{io::Write, mem::MaybeUninit, sync::Arc};
+use std::{
+    io::Write,
+    mem::MaybeUninit,
+    sync::{atomic::Ordering, Arc},
+};
 
-use crate::{listening::listen_podcast, BUFFER_LENGTH};
+use crate::{
+    listener_address,
+    listening::listen_podcast,
+    messaging::{socket_writer, Messaging},
+    quality::{quality_beacon, QualityCounters},
+    BUFFER_LENGTH,
+};
 
 pub async fn start_listening(
     mut is_maintaining: Signal<(bool, bool)>,
     mut is_listening: Signal<bool>,
+    mut messaging: Messaging,
 ) {
     if is_listening() {
         log::info!("Trying Sir");
-        let connect_addr = "ws://192.168.1.2:2424";
+        let connect_addr = listener_address();
 
         let ws_stream: tokio_tungstenite_wasm::WebSocketStream;
-        match tokio_tungstenite_wasm::connect(connect_addr).await {
+        match tokio_tungstenite_wasm::connect(connect_addr.as_str()).await {
             Ok(ws_stream_connected) => ws_stream = ws_stream_connected,
             Err(_) => {
                 is_listening.set(false);
@@ -28,16 +39,32 @@ pub async fn start_listening(
         is_maintaining.set((true, true));
         let ring = HeapRb::<f32>::new(BUFFER_LENGTH);
         let (producer, consumer) = ring.split();
+        let quality = Arc::new(QualityCounters::default());
+        let (ws_sink, ws_stream) = ws_stream.split();
+        let (outbox, outbox_receiver) = futures_channel::mpsc::unbounded();
+        messaging.outbox.set(Some(outbox.clone()));
+        let _socket_writer_task = spawn(async move {
+            socket_writer(outbox_receiver, ws_sink).await;
+        });
+        let _quality_beacon_task = spawn({
+            let quality = quality.clone();
+            async move {
+                quality_beacon(is_listening, quality, outbox).await;
+            }
+        });
         let _sound_stream_task = spawn({
+            let quality = quality.clone();
             async move {
-                sound_stream(is_listening, ws_stream, producer).await;
+                sound_stream(is_listening, ws_stream, producer, quality, messaging).await;
+                // the writer winds down once the last sender is gone
+                messaging.outbox.set(None);
                 is_listening.set(false);
                 is_maintaining.set((false, is_maintaining().1));
             }
         });
         let _listen_podcast_task = spawn({
             async move {
-                listen_podcast(is_listening, consumer).await;
+                listen_podcast(is_listening, consumer, quality).await;
                 is_listening.set(false);
                 //stream_producer.send("Disconnect ME".into()).await.unwrap();
                 is_maintaining.set((is_maintaining().0, false));
@@ -48,18 +75,39 @@ pub async fn start_listening(
 
 pub async fn sound_stream(
     is_listening: Signal<bool>,
-    mut ws_stream: tokio_tungstenite_wasm::WebSocketStream,
+    mut ws_stream: SplitStream<tokio_tungstenite_wasm::WebSocketStream>,
     mut producer: Producer<f32, Arc<SharedRb<f32, Vec<MaybeUninit<f32>>>>>,
+    quality: Arc<QualityCounters>,
+    mut messaging: Messaging,
 ) {
     log::info!("Attention! We need cables");
 
     while let Some(message_with_question) = ws_stream.next().await {
         if is_listening() {
             //log::info!("{}", message_with_question.unwrap().len());
-            let data: Vec<u8> = message_with_question.unwrap().into();
+            let message = match message_with_question {
+                Ok(message) => message,
+                Err(err_val) => {
+                    log::error!("Error: Message | {}", err_val);
+                    break;
+                }
+            };
+            if let tokio_tungstenite_wasm::Message::Close(close_frame) = &message {
+                if let Some(close_frame) = close_frame {
+                    log::info!("Connection Closed Sir: {}", close_frame.reason);
+                }
+                break;
+            }
+            // chat shares the socket, only binary frames carry sound
+            if let tokio_tungstenite_wasm::Message::Text(text) = &message {
+                messaging.receive(text);
+                continue;
+            }
+            let data: Vec<u8> = message.into();
             let mut decompression_writer = DecompressorWriter::new(vec![], BUFFER_LENGTH);
             if let Err(err_val) = decompression_writer.write_all(&data) {
                 log::error!("Error: Decompression | {}", err_val);
+                quality.decode_errors.fetch_add(1, Ordering::Relaxed);
             }
             let uncompressed_data = match decompression_writer.into_inner() {
                 Ok(healthy_packet) => healthy_packet,
@@ -69,7 +117,14 @@ pub async fn sound_stream(
                 }
             };
             log::info!("{}", uncompressed_data.len());
-            let data = String::from_utf8(uncompressed_data).unwrap();
+            let data = match String::from_utf8(uncompressed_data) {
+                Ok(data) => data,
+                Err(err_val) => {
+                    log::warn!("Warning: Unhealthy Packet | {}", err_val);
+                    quality.decode_errors.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
+            };
             let mut datum_parsed: Vec<char> = vec![];
             let mut data_parsed: Vec<String> = vec![];
             for char in data.chars() {
@@ -86,13 +141,23 @@ pub async fn sound_stream(
                 }
             }
 
+            quality
+                .packet_length
+                .store(data_parsed.len(), Ordering::Relaxed);
+            let mut is_dropped = false;
             for single_data in data_parsed {
                 let sample = match single_data.parse::<f32>() {
                     Ok(sample) => sample,
                     Err(_) => 0.0,
                 };
-                if let Err(_) = producer.push(sample) {}
+                if let Err(_) = producer.push(sample) {
+                    is_dropped = true;
+                }
+            }
+            if is_dropped {
+                quality.dropped_packets.fetch_add(1, Ordering::Relaxed);
             }
+            quality.buffered.store(producer.len(), Ordering::Relaxed);
         } else {
             break;
         }
@@ -7,17 +7,18 @@ use std::{
 
 use iced::{
     alignment,
-    widget::{column, container, row, scrollable, slider, text::LineHeight, Container, Rule},
+    widget::{column, container, row, scrollable, slider, text, text::LineHeight, Container, Rule},
     window::{self},
     Color, Length, Subscription, Task,
 };
-use tokio::sync::broadcast::{channel, Receiver, Sender};
+use tokio::sync::broadcast::{channel, error::RecvError, Receiver, Sender};
 
 use crate::{
     gui_components::{button_with_centered_text, text_centered},
-    gui_utils::{self, change_audio_volume, change_microphone_volume},
+    gui_utils::{self, change_audio_volume, change_call_volume, change_microphone_volume},
     utils::get_config,
-    Config, AUDIO_BUFFER_SIZE, AUDIO_PATH, AUDIO_SCROLLABLE_BUTTON_SIZE, BUFFER_LENGTH,
+    CallNotice, Config, SongRequest, AUDIO_BUFFER_SIZE, AUDIO_PATH, AUDIO_SCROLLABLE_BUTTON_SIZE,
+    BUFFER_LENGTH, CALL_QUEUE_LENGTH, CHAT_LOG_LENGTH, SONG_REQUEST_QUEUE_LENGTH,
     WINDOW_SIZE_WIDTH,
 };
 
@@ -28,6 +29,13 @@ pub enum Player {
     Stop,
 }
 
+#[derive(Debug, Clone)]
+pub enum SongDecision {
+    Accept,
+    Reject,
+    Enqueue,
+}
+
 #[derive(Debug, Clone)]
 struct Features {
     stream: bool,
@@ -59,8 +67,15 @@ pub enum Event {
     ChooseAudio(String),
     ChangeMicrophoneVolume(f32),
     ChangeAudioVolume(f32),
+    ChangeCallVolume(f32),
     LoadConfig(Config),
     ListFiles(Option<Vec<String>>),
+    ChatReceived(String),
+    SongRequestReceived(SongRequest),
+    CallNoticeReceived(CallNotice),
+    AcceptCall(u64),
+    HangUpCall(u64),
+    DecideSongRequest(SongRequest, SongDecision),
     IcedEvent(iced::Event),
     CloseWindow(window::Id),
 }
@@ -78,6 +93,7 @@ pub enum State {
     ContinuedAudio,
     MicrophoneVolumeChanged,
     AudioVolumeChanged,
+    CallVolumeChanged,
 }
 
 #[derive(Debug, Clone)]
@@ -89,6 +105,11 @@ pub enum Message {
 struct DataChannel {
     microphone_stream_sender: Sender<f32>,
     audio_stream_sender: Sender<f32>,
+    chat_stream_sender: Sender<String>,
+    song_request_stream_sender: Sender<SongRequest>,
+    streamer_message_sender: Sender<String>,
+    call_stream_sender: Sender<f32>,
+    call_notice_sender: Sender<CallNotice>,
 }
 #[derive(Debug)]
 struct CommunicationChannel {
@@ -120,6 +141,12 @@ struct GUIStatus {
     are_we_paused_audio: Condition,
     microphone_volume: ChangeableValue,
     audio_volume: ChangeableValue,
+    call_volume: ChangeableValue,
+    chat_log: Vec<String>,
+    song_requests: Vec<SongRequest>,
+    up_next: Vec<SongRequest>,
+    callers: Vec<(u64, String)>,
+    on_air_caller: Option<u64>,
 }
 #[derive(Debug)]
 pub struct Streamer {
@@ -138,6 +165,11 @@ impl Streamer {
                 data_channel: DataChannel {
                     microphone_stream_sender: channel(BUFFER_LENGTH).0,
                     audio_stream_sender: channel(BUFFER_LENGTH).0,
+                    chat_stream_sender: channel(CHAT_LOG_LENGTH).0,
+                    song_request_stream_sender: channel(SONG_REQUEST_QUEUE_LENGTH).0,
+                    streamer_message_sender: channel(SONG_REQUEST_QUEUE_LENGTH).0,
+                    call_stream_sender: channel(BUFFER_LENGTH).0,
+                    call_notice_sender: channel(CALL_QUEUE_LENGTH).0,
                 },
                 communication_channel: CommunicationChannel {
                     base_to_streaming_sender: channel(1).0,
@@ -167,6 +199,14 @@ impl Streamer {
                     audio_volume: ChangeableValue {
                         value: Arc::new(1.0.into()),
                     },
+                    call_volume: ChangeableValue {
+                        value: Arc::new(1.0.into()),
+                    },
+                    chat_log: vec![],
+                    song_requests: vec![],
+                    up_next: vec![],
+                    callers: vec![],
+                    on_air_caller: None,
                 },
             },
             Task::perform(
@@ -198,6 +238,14 @@ impl Streamer {
                         .subscribe();
                     let microphone_stream_volume = self.gui_status.microphone_volume.value.clone();
                     let audio_stream_volume = self.gui_status.audio_volume.value.clone();
+                    let chat_stream_sender = self.data_channel.chat_stream_sender.clone();
+                    let call_stream_sender = self.data_channel.call_stream_sender.clone();
+                    let call_notice_sender = self.data_channel.call_notice_sender.clone();
+                    let call_stream_volume = self.gui_status.call_volume.value.clone();
+                    let song_request_stream_sender =
+                        self.data_channel.song_request_stream_sender.clone();
+                    let streamer_message_receiver =
+                        self.data_channel.streamer_message_sender.subscribe();
                     let streaming_to_base_sender_is_finished = self
                         .communication_channel
                         .streaming_to_base_is_finished
@@ -224,6 +272,12 @@ impl Streamer {
                                 streaming_to_base_sender_is_finished,
                                 microphone_stream_volume,
                                 audio_stream_volume,
+                                chat_stream_sender,
+                                song_request_stream_sender,
+                                streamer_message_receiver,
+                                call_stream_sender,
+                                call_notice_sender,
+                                call_stream_volume,
                             )
                             .await
                         },
@@ -503,6 +557,13 @@ impl Streamer {
                         Message::State,
                     )
                 }
+                Event::ChangeCallVolume(value) => {
+                    let call_volume = self.gui_status.call_volume.value.clone();
+                    Task::perform(
+                        async move { change_call_volume(value, call_volume).await },
+                        Message::State,
+                    )
+                }
                 Event::LoadConfig(config) => {
                     self.config = Some(config);
                     Task::none()
@@ -511,6 +572,91 @@ impl Streamer {
                     self.audio_miscellaneous.files = files;
                     Task::none()
                 }
+                Event::ChatReceived(chat_line) => {
+                    if self.gui_status.chat_log.len() == CHAT_LOG_LENGTH {
+                        self.gui_status.chat_log.remove(0);
+                    }
+                    self.gui_status.chat_log.push(chat_line);
+                    Task::none()
+                }
+                Event::SongRequestReceived(song_request) => {
+                    // the relay repeats what is still pending whenever we reconnect
+                    if self
+                        .gui_status
+                        .song_requests
+                        .iter()
+                        .any(|pending| pending.id == song_request.id)
+                    {
+                        return Task::none();
+                    }
+                    if self.gui_status.song_requests.len() == SONG_REQUEST_QUEUE_LENGTH {
+                        self.gui_status.song_requests.remove(0);
+                    }
+                    self.gui_status.song_requests.push(song_request);
+                    Task::none()
+                }
+                Event::CallNoticeReceived(call_notice) => {
+                    match call_notice {
+                        CallNotice::Waiting { id, name } => {
+                            // the relay repeats who is still waiting whenever we reconnect
+                            if !self.gui_status.callers.iter().any(|caller| caller.0 == id) {
+                                self.gui_status.callers.push((id, name));
+                            }
+                        }
+                        CallNotice::Ended { id } => {
+                            self.gui_status.callers.retain(|caller| caller.0 != id);
+                            if self.gui_status.on_air_caller == Some(id) {
+                                self.gui_status.on_air_caller = None;
+                            }
+                        }
+                    }
+                    Task::none()
+                }
+                Event::AcceptCall(id) => {
+                    self.gui_status.on_air_caller = Some(id);
+                    self.send_call_decision(id, "accept");
+                    Task::none()
+                }
+                Event::HangUpCall(id) => {
+                    self.send_call_decision(id, "hang_up");
+                    Task::none()
+                }
+                Event::DecideSongRequest(song_request, song_decision) => {
+                    self.gui_status
+                        .song_requests
+                        .retain(|pending| pending.id != song_request.id);
+                    self.gui_status
+                        .up_next
+                        .retain(|queued| queued.id != song_request.id);
+                    let decision = match song_decision {
+                        SongDecision::Accept => "accepted",
+                        SongDecision::Reject => "rejected",
+                        SongDecision::Enqueue => "queued",
+                    };
+                    let song_decision_json = serde_json::json!({
+                        "type":"song_decision",
+                        "id":song_request.id,
+                        "decision":decision,
+                    });
+                    if let Err(err_val) = self
+                        .data_channel
+                        .streamer_message_sender
+                        .send(song_decision_json.to_string())
+                    {
+                        eprintln!("Error: Communication | Song Decision | Send | {}", err_val);
+                    }
+                    match song_decision {
+                        SongDecision::Accept if song_request.from_library => Task::perform(
+                            async move { Event::ChooseAudio(song_request.song) },
+                            Message::Event,
+                        ),
+                        SongDecision::Enqueue => {
+                            self.gui_status.up_next.push(song_request);
+                            Task::none()
+                        }
+                        _ => Task::none(),
+                    }
+                }
                 Event::IcedEvent(iced_event) => match iced_event {
                     iced::Event::Keyboard(_) => Task::perform(
                         async move {
@@ -556,6 +702,9 @@ impl Streamer {
                 }
                 State::Disconnected => {
                     self.gui_status.are_we_connect = Condition::Passive;
+                    // the relay hangs up on every caller once we are gone
+                    self.gui_status.callers.clear();
+                    self.gui_status.on_air_caller = None;
                     Task::none()
                 }
                 State::Recording => {
@@ -588,6 +737,7 @@ impl Streamer {
                 }
                 State::MicrophoneVolumeChanged => Task::none(),
                 State::AudioVolumeChanged => Task::none(),
+                State::CallVolumeChanged => Task::none(),
             },
         }
     }
@@ -703,6 +853,13 @@ impl Streamer {
         )
         .step(0.01);
 
+        let call_volume_slider = slider(
+            0.0..=1.0,
+            *self.gui_status.call_volume.value.lock().unwrap(),
+            |value| Message::Event(Event::ChangeCallVolume(value)),
+        )
+        .step(0.01);
+
         let audio_file_size_for_scrollable = match self.audio_miscellaneous.files.as_ref() {
             Some(audio_files) => audio_files.len() as u16 * (AUDIO_SCROLLABLE_BUTTON_SIZE + 1),
             None => 0,
@@ -747,6 +904,69 @@ impl Streamer {
         let audio_info_content = column![audio_selected, audio_playing,]
             .height(100)
             .width(longest_audio_name);
+        let mut chat_scrollable_content = column![].spacing(1).width(WINDOW_SIZE_WIDTH);
+        for chat_line in &self.gui_status.chat_log {
+            chat_scrollable_content = chat_scrollable_content.push(text(chat_line.clone()));
+        }
+        let chat_scrollable = scrollable(chat_scrollable_content)
+            .anchor_bottom()
+            .height(120)
+            .width(WINDOW_SIZE_WIDTH);
+        let mut song_requests_scrollable_content = column![].spacing(1).width(WINDOW_SIZE_WIDTH);
+        for song_request in &self.gui_status.song_requests {
+            let song_request_row = row![
+                text(song_request.song.clone()).width(Length::FillPortion(3)),
+                button_with_centered_text("Accept").on_press(Message::Event(
+                    Event::DecideSongRequest(song_request.clone(), SongDecision::Accept)
+                )),
+                button_with_centered_text("Reject").on_press(Message::Event(
+                    Event::DecideSongRequest(song_request.clone(), SongDecision::Reject)
+                )),
+                button_with_centered_text("Enqueue").on_press(Message::Event(
+                    Event::DecideSongRequest(song_request.clone(), SongDecision::Enqueue)
+                )),
+            ]
+            .spacing(5)
+            .height(AUDIO_SCROLLABLE_BUTTON_SIZE);
+            song_requests_scrollable_content =
+                song_requests_scrollable_content.push(song_request_row);
+        }
+        let song_requests_scrollable = scrollable(song_requests_scrollable_content)
+            .height(120)
+            .width(WINDOW_SIZE_WIDTH);
+        // accepting a queued request takes it off the list and picks it if we have it
+        let mut up_next_content = row![text_centered("Up Next")].spacing(5);
+        for queued in &self.gui_status.up_next {
+            up_next_content = up_next_content.push(
+                button_with_centered_text(queued.song.clone()).on_press(Message::Event(
+                    Event::DecideSongRequest(queued.clone(), SongDecision::Accept),
+                )),
+            );
+        }
+        let up_next_scrollable = scrollable(up_next_content)
+            .direction(scrollable::Direction::Horizontal(Default::default()))
+            .height(AUDIO_SCROLLABLE_BUTTON_SIZE)
+            .width(WINDOW_SIZE_WIDTH);
+        let mut callers_content = column![].spacing(1).width(WINDOW_SIZE_WIDTH);
+        for (id, name) in &self.gui_status.callers {
+            let accept_button = if self.gui_status.on_air_caller == Some(*id) {
+                button_with_centered_text("On Air")
+            } else {
+                button_with_centered_text("Accept").on_press(Message::Event(Event::AcceptCall(*id)))
+            };
+            let caller_row = row![
+                text(name.clone()).width(Length::FillPortion(3)),
+                accept_button,
+                button_with_centered_text("Hang Up")
+                    .on_press(Message::Event(Event::HangUpCall(*id))),
+            ]
+            .spacing(5)
+            .height(AUDIO_SCROLLABLE_BUTTON_SIZE);
+            callers_content = callers_content.push(caller_row);
+        }
+        let callers_scrollable = scrollable(callers_content)
+            .height(80)
+            .width(WINDOW_SIZE_WIDTH);
         let header_content = row![header].width(WINDOW_SIZE_WIDTH).height(50);
         let text_content = row![
             connection_text,
@@ -782,10 +1002,14 @@ impl Streamer {
         .spacing(5)
         .width(WINDOW_SIZE_WIDTH)
         .height(35);
-        let volume_content = row![microphone_volume_slider, audio_volume_slider,]
-            .spacing(5)
-            .width(WINDOW_SIZE_WIDTH)
-            .height(35);
+        let volume_content = row![
+            microphone_volume_slider,
+            audio_volume_slider,
+            call_volume_slider,
+        ]
+        .spacing(5)
+        .width(WINDOW_SIZE_WIDTH)
+        .height(35);
         let content = column![
             header_content,
             Rule::horizontal(1),
@@ -796,6 +1020,13 @@ impl Streamer {
             volume_content,
             audios_scrollable,
             audio_info_content,
+            Rule::horizontal(1),
+            song_requests_scrollable,
+            up_next_scrollable,
+            Rule::horizontal(1),
+            callers_scrollable,
+            Rule::horizontal(1),
+            chat_scrollable,
         ]
         .spacing(20)
         .width(Length::Fill)
@@ -806,9 +1037,32 @@ impl Streamer {
             .align_y(alignment::Vertical::Top)
     }
     pub fn subscription(&self) -> Subscription<Message> {
-        iced::event::listen()
-            .map(Event::IcedEvent)
-            .map(Message::Event)
+        let chat_subscription = Subscription::run_with_id(
+            "chat",
+            receiver_stream(self.data_channel.chat_stream_sender.subscribe()),
+        )
+        .map(Event::ChatReceived)
+        .map(Message::Event);
+        let song_request_subscription = Subscription::run_with_id(
+            "song_requests",
+            receiver_stream(self.data_channel.song_request_stream_sender.subscribe()),
+        )
+        .map(Event::SongRequestReceived)
+        .map(Message::Event);
+        let call_notice_subscription = Subscription::run_with_id(
+            "call_notices",
+            receiver_stream(self.data_channel.call_notice_sender.subscribe()),
+        )
+        .map(Event::CallNoticeReceived)
+        .map(Message::Event);
+        Subscription::batch(vec![
+            iced::event::listen()
+                .map(Event::IcedEvent)
+                .map(Message::Event),
+            chat_subscription,
+            song_request_subscription,
+            call_notice_subscription,
+        ])
     }
     pub fn load_config() -> Task<Message> {
         Task::perform(
@@ -828,6 +1082,20 @@ impl Streamer {
             Message::Event,
         )
     }
+    fn send_call_decision(&self, id: u64, decision: &str) {
+        let call_decision_json = serde_json::json!({
+            "type":"call_decision",
+            "id":id,
+            "decision":decision,
+        });
+        if let Err(err_val) = self
+            .data_channel
+            .streamer_message_sender
+            .send(call_decision_json.to_string())
+        {
+            eprintln!("Error: Communication | Call Decision | Send | {}", err_val);
+        }
+    }
     fn call_closer(
         streaming_to_base_receiver: Receiver<bool>,
         base_to_streaming_sender: Sender<bool>,
@@ -896,3 +1164,17 @@ impl Streamer {
         )
     }
 }
+
+fn receiver_stream<T: Clone + Send + 'static>(
+    receiver: Receiver<T>,
+) -> impl futures_util::Stream<Item = T> {
+    futures_util::stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(received) => return Some((received, receiver)),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
+}
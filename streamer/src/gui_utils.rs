@@ -9,7 +9,7 @@ use tokio::sync::broadcast::{Receiver, Sender};
 
 use crate::{
     gui::{Player, State},
-    playing, recording, streaming, Config,
+    playing, recording, streaming, CallNotice, Config, SongRequest,
 };
 
 pub async fn connect(
@@ -21,11 +21,23 @@ pub async fn connect(
     streaming_to_base_sender_is_finished: Sender<bool>,
     microphone_stream_volume: Arc<Mutex<f32>>,
     audio_stream_volume: Arc<Mutex<f32>>,
+    chat_stream_sender: Sender<String>,
+    song_request_stream_sender: Sender<SongRequest>,
+    streamer_message_receiver: Receiver<String>,
+    call_stream_sender: Sender<f32>,
+    call_notice_sender: Sender<CallNotice>,
+    call_stream_volume: Arc<Mutex<f32>>,
 ) -> State {
     let mut streaming_to_base_receiver = streaming_to_base_sender.subscribe();
     tokio::spawn(streaming::connect(
         microphone_stream_receiver,
         audio_stream_receiver,
+        chat_stream_sender,
+        song_request_stream_sender,
+        streamer_message_receiver,
+        call_stream_sender,
+        call_notice_sender,
+        call_stream_volume,
         streamer_config,
         base_to_streaming_receiver,
         streaming_to_base_sender.clone(),
@@ -364,6 +376,11 @@ pub async fn change_audio_volume(
     State::AudioVolumeChanged
 }
 
+pub async fn change_call_volume(desired_value: f32, call_stream_volume: Arc<Mutex<f32>>) -> State {
+    *call_stream_volume.lock().unwrap() = desired_value;
+    State::CallVolumeChanged
+}
+
 pub async fn list_files(folder_path: &Path) -> Option<Vec<String>> {
     let mut file_names: Vec<String> = vec![];
     match std::fs::read_dir(folder_path) {
@@ -11,7 +11,10 @@ pub const AUDIO_PATH: &str = "audio";
 pub const AUDIO_BUFFER_SIZE: usize = 1048576;
 pub const AUDIO_SCROLLABLE_BUTTON_SIZE: u16 = 35;
 pub const WINDOW_SIZE_WIDTH: u16 = 450;
-pub const WINDOW_SIZE_HEIGHT: u16 = 650;
+pub const WINDOW_SIZE_HEIGHT: u16 = 1100;
+pub const CHAT_LOG_LENGTH: usize = 100;
+pub const SONG_REQUEST_QUEUE_LENGTH: usize = 100;
+pub const CALL_QUEUE_LENGTH: usize = 16;
 
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -19,4 +22,18 @@ pub struct Config {
     pub quality: u8,
     pub latency: u16,
     pub tls: bool,
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SongRequest {
+    pub id: u64,
+    pub song: String,
+    pub from_library: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum CallNotice {
+    Waiting { id: u64, name: String },
+    Ended { id: u64 },
 }
@@ -39,6 +39,17 @@ pub async fn record(
     tokio::spawn(let_the_base_know(recording_to_base.clone()));
     println!("Recording Stopped");
 }
+// everything sent to the relay is in the microphone's layout
+pub fn microphone_config() -> Option<cpal::StreamConfig> {
+    let input_device = cpal::default_host().default_input_device()?;
+    match input_device.default_input_config() {
+        Ok(input_config) => Some(input_config.into()),
+        Err(err_val) => {
+            eprintln!("Error: Microphone Config | {}", err_val);
+            None
+        }
+    }
+}
 fn err_fn(err: cpal::StreamError) {
     eprintln!("Something Happened: {}", err);
 }
@@ -1,25 +1,113 @@
 use std::{
     cmp::min,
-    io::Write,
+    io::{Read, Write},
+    path::Path,
     sync::{Arc, Mutex},
     time::Duration,
 };
 
-use brotli::CompressorWriter;
-use futures_util::SinkExt;
-use ringbuf::HeapRb;
+use brotli::{CompressorWriter, Decompressor};
+use futures_util::{SinkExt, StreamExt};
+use rubato::{
+    Resampler, SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction,
+};
 use tokio::{
     sync::broadcast::{channel, Receiver, Sender},
     task::JoinHandle,
 };
-use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::{Error, Message};
 
-use crate::{Config, BUFFER_LENGTH};
+use crate::{gui_utils, recording, CallNotice, Config, SongRequest, AUDIO_PATH, BUFFER_LENGTH};
 const MAX_TOLERATED_MESSAGE_COUNT: usize = 10;
+// what the front sends a caller in as
+const CALL_SAMPLE_RATE: u32 = 48000;
+const CALL_CHANNELS: usize = 2;
+const CALL_CHUNK_SIZE: usize = 1024;
+
+// callers arrive in the front's layout, the mix runs in the microphone's
+struct CallConverter {
+    channels: usize,
+    resampler: Option<SincFixedIn<f64>>,
+    pending: Vec<f64>,
+}
+
+impl CallConverter {
+    fn new(sample_rate: u32, channels: usize) -> Option<CallConverter> {
+        let resampler = match sample_rate == CALL_SAMPLE_RATE {
+            true => None,
+            false => {
+                let params = SincInterpolationParameters {
+                    sinc_len: 256,
+                    f_cutoff: 0.95,
+                    interpolation: SincInterpolationType::Linear,
+                    oversampling_factor: 128,
+                    window: WindowFunction::BlackmanHarris2,
+                };
+                match SincFixedIn::<f64>::new(
+                    sample_rate as f64 / CALL_SAMPLE_RATE as f64,
+                    2.0,
+                    params,
+                    CALL_CHUNK_SIZE,
+                    1,
+                ) {
+                    Ok(resampler) => Some(resampler),
+                    Err(err_val) => {
+                        eprintln!("Error: Call Resampler | {}", err_val);
+                        return None;
+                    }
+                }
+            }
+        };
+        Some(CallConverter {
+            channels: channels.max(1),
+            resampler,
+            pending: vec![],
+        })
+    }
+
+    // the caller is folded down to one channel, resampled, then spread over ours
+    fn convert(&mut self, call_data: &[f32]) -> Vec<f32> {
+        self.pending
+            .extend(call_data.chunks(CALL_CHANNELS).map(|call_frame| {
+                call_frame
+                    .iter()
+                    .map(|&call_datum| call_datum as f64)
+                    .sum::<f64>()
+                    / call_frame.len() as f64
+            }));
+        let converted = match &mut self.resampler {
+            None => std::mem::take(&mut self.pending),
+            Some(resampler) => {
+                let mut converted = vec![];
+                while self.pending.len() >= resampler.input_frames_next() {
+                    let chunk: Vec<f64> = self
+                        .pending
+                        .drain(..resampler.input_frames_next())
+                        .collect();
+                    match resampler.process(&[chunk], None) {
+                        Ok(mut resampled) => converted.append(&mut resampled[0]),
+                        Err(err_val) => eprintln!("Error: Call Resample | {}", err_val),
+                    }
+                }
+                converted
+            }
+        };
+        converted
+            .into_iter()
+            .flat_map(|call_datum| std::iter::repeat_n(call_datum as f32, self.channels))
+            .collect()
+    }
+}
 
 pub async fn connect(
     microphone_stream_receiver: Receiver<f32>,
     audio_stream_receiver: Receiver<f32>,
+    chat_stream_sender: Sender<String>,
+    song_request_stream_sender: Sender<SongRequest>,
+    streamer_message_receiver: Receiver<String>,
+    call_stream_sender: Sender<f32>,
+    call_notice_sender: Sender<CallNotice>,
+    call_stream_volume: Arc<Mutex<f32>>,
     streamer_config: Config,
     mut base_to_streaming: Receiver<bool>,
     streaming_to_base: Sender<bool>,
@@ -31,9 +119,14 @@ pub async fn connect(
         true => format!("wss://{}", streamer_config.address),
         false => format!("ws://{}", streamer_config.address),
     };
+    // the relay wants the token once it guards the streamer port, co-hosts share it too
+    let connect_addr = match &streamer_config.token {
+        Some(token) => format!("{}/?token={}", connect_addr, token),
+        None => connect_addr,
+    };
 
     if let Err(_) = base_to_streaming.try_recv() {
-        let ws_stream;
+        let mut ws_stream;
         match streamer_config.tls {
             true => {
                 let tls_client_config = rustls_platform_verifier::tls_config();
@@ -85,8 +178,10 @@ pub async fn connect(
         let mixer_task = tokio::spawn(mixer(
             microphone_stream_receiver,
             audio_stream_receiver,
+            call_stream_sender.subscribe(),
             microphone_stream_volume,
             audio_stream_volume,
+            call_stream_volume,
             flow_sender,
             streamer_config.latency,
         ));
@@ -96,12 +191,42 @@ pub async fn connect(
             streamer_config.quality,
             streamer_config.latency,
         ));
-        let stream_task = tokio::spawn(stream(ws_stream, message_consumer));
+        // listeners pick requests from whatever sits in the audio folder
+        if let Some(songs) = gui_utils::list_files(Path::new(AUDIO_PATH)).await {
+            let library = serde_json::json!({
+                "type":"library",
+                "songs":songs,
+            });
+            if let Err(err_val) = ws_stream.send(Message::Text(library.to_string())).await {
+                eprintln!("Error: Library | {}", err_val);
+            }
+        }
+        let (ws_sender, ws_receiver) = ws_stream.split();
+        let stream_task = tokio::spawn(stream(
+            ws_sender,
+            message_consumer,
+            streamer_message_receiver,
+        ));
+        let call_converter = recording::microphone_config().and_then(|microphone_config| {
+            CallConverter::new(
+                microphone_config.sample_rate.0,
+                microphone_config.channels.into(),
+            )
+        });
+        let relay_message_task = tokio::spawn(relay_message(
+            ws_receiver,
+            chat_stream_sender,
+            song_request_stream_sender,
+            call_stream_sender,
+            call_notice_sender,
+            call_converter,
+        ));
         let _ = streaming_to_base.send(true);
         tokio::spawn(status_checker(
             message_organizer_task,
             stream_task,
             mixer_task,
+            relay_message_task,
             base_to_streaming,
             streaming_to_base,
             streaming_to_base_sender_is_finished,
@@ -111,8 +236,10 @@ pub async fn connect(
 async fn mixer(
     mut microphone_stream_receiver: Receiver<f32>,
     mut audio_stream_receiver: Receiver<f32>,
+    mut call_stream_receiver: Receiver<f32>,
     microphone_stream_volume: Arc<Mutex<f32>>,
     audio_stream_volume: Arc<Mutex<f32>>,
+    call_stream_volume: Arc<Mutex<f32>>,
     flow_sender: Sender<f32>,
     latency: u16,
 ) {
@@ -158,9 +285,25 @@ async fn mixer(
             }
         }
 
+        // the caller runs on its own clock, take whatever arrived since the last round
+        let mut call_stream = vec![];
+        let mut call_stream_iteration = call_stream_receiver.len();
+        while call_stream_iteration > 0 {
+            call_stream_iteration -= 1;
+            match call_stream_receiver.recv().await {
+                Ok(call_datum) => {
+                    call_stream.push(call_datum);
+                }
+                Err(err_val) => {
+                    eprintln!("Error: Communication | Call Stream | Recv | {}", err_val);
+                }
+            }
+        }
+
         let mut flow = vec![];
         let microphone_volume = *microphone_stream_volume.lock().unwrap();
         let audio_volume = *audio_stream_volume.lock().unwrap();
+        let call_volume = *call_stream_volume.lock().unwrap();
 
         for element in microphone_stream {
             if element < 0.01 || element > -0.01 {
@@ -177,6 +320,15 @@ async fn mixer(
             }
         }
 
+        for (i, element) in call_stream.iter().enumerate() {
+            let call_volumized = element * call_volume;
+            if flow.len() > i {
+                flow[i] = flow[i] + call_volumized;
+            } else {
+                flow.push(call_volumized);
+            }
+        }
+
         for i in 0..flow.len() {
             if flow[i] > 1.0 {
                 flow[i] = 0.5 * (flow[i] / flow[i].trunc() * 10.0);
@@ -205,32 +357,7 @@ async fn message_organizer(
         while iteration > 0 {
             iteration -= 1;
             match flow_receiver.recv().await {
-                Ok(single_data) => {
-                    let ring = HeapRb::<u8>::new(BUFFER_LENGTH);
-                    let (mut producer, mut flow_receiver) = ring.split();
-                    let mut charred: Vec<char> = single_data.to_string().chars().collect();
-                    if charred[0] == '0' {
-                        charred.insert(0, '+');
-                    }
-                    if charred.len() > 2 {
-                        let _zero = charred.remove(1);
-                        let _point = charred.remove(1);
-                    }
-                    charred.truncate(quality.into());
-                    let mut single_data_packet: Vec<u8> = vec![];
-                    for char in charred {
-                        let char_packet = char.to_string().as_bytes().to_vec();
-                        for byte in char_packet {
-                            single_data_packet.push(byte);
-                        }
-                    }
-                    for element in single_data_packet {
-                        producer.push(element).unwrap();
-                    }
-                    while !flow_receiver.is_empty() {
-                        messages.push(flow_receiver.pop().unwrap());
-                    }
-                }
+                Ok(single_data) => messages.extend(sample_packet(single_data, quality)),
                 Err(_) => {}
             }
         }
@@ -249,11 +376,40 @@ async fn message_organizer(
     }
 }
 
+// "+1234" is 0.1234, every sample signed and as wide as the quality allows so the relay
+// can always tell where one ends, anything out of range is clipped to fit and an f32 has
+// no more than seven digits to give
+fn sample_packet(sample: f32, quality: u8) -> Vec<u8> {
+    let digits = usize::from(quality.clamp(2, 8)) - 1;
+    let largest = 1.0 - 10_f32.powi(-(digits as i32));
+    let sample = match sample.is_nan() {
+        true => 0.0,
+        false => sample.clamp(-largest, largest),
+    };
+    let formatted = format!("{:+.*}", digits, sample);
+    let (sign, magnitude) = formatted.split_at(1);
+    let fraction = magnitude
+        .split_once('.')
+        .map_or("", |(_, fraction)| fraction);
+    format!("{}{}", sign, fraction).into_bytes()
+}
+
 async fn stream<T: futures_util::Sink<Message> + std::marker::Unpin>(
     mut ws_stream: T,
     mut message_consumer: Receiver<Message>,
+    mut streamer_message_receiver: Receiver<String>,
 ) {
-    while let Ok(message) = message_consumer.recv().await {
+    loop {
+        let message = tokio::select! {
+            message = message_consumer.recv() => match message {
+                Ok(message) => message,
+                Err(_) => break,
+            },
+            streamer_message = streamer_message_receiver.recv() => match streamer_message {
+                Ok(streamer_message) => Message::Text(streamer_message),
+                Err(_) => continue,
+            },
+        };
         if message_consumer.len() > MAX_TOLERATED_MESSAGE_COUNT {
             break;
         }
@@ -270,10 +426,111 @@ async fn stream<T: futures_util::Sink<Message> + std::marker::Unpin>(
     }
 }
 
+async fn relay_message<
+    T: futures_util::Stream<Item = Result<Message, Error>> + std::marker::Unpin,
+>(
+    mut ws_stream: T,
+    chat_stream_sender: Sender<String>,
+    song_request_stream_sender: Sender<SongRequest>,
+    call_stream_sender: Sender<f32>,
+    call_notice_sender: Sender<CallNotice>,
+    mut call_converter: Option<CallConverter>,
+) {
+    while let Some(Ok(message)) = ws_stream.next().await {
+        // the only binary the relay sends us is the caller on air
+        if let Message::Binary(call_frame) = &message {
+            match call_converter.as_mut() {
+                Some(call_converter) => {
+                    for call_datum in call_converter.convert(&decode_call_frame(call_frame)) {
+                        let _ = call_stream_sender.send(call_datum);
+                    }
+                }
+                None => eprintln!("Error: Call Frame | Microphone Layout Unknown"),
+            }
+        }
+        if let Message::Text(relay_message) = message {
+            let relay_message: serde_json::Value = match serde_json::from_str(&relay_message) {
+                Ok(relay_message) => relay_message,
+                Err(err_val) => {
+                    eprintln!("Error: Relay Message | {}", err_val);
+                    continue;
+                }
+            };
+            match relay_message["type"].as_str() {
+                Some("chat") => {
+                    if let (Some(nick), Some(text)) = (
+                        relay_message["nick"].as_str(),
+                        relay_message["text"].as_str(),
+                    ) {
+                        let _ = chat_stream_sender.send(format!("{}: {}", nick, text));
+                    }
+                }
+                Some("song_request") => {
+                    if let (Some(id), Some(song)) =
+                        (relay_message["id"].as_u64(), relay_message["song"].as_str())
+                    {
+                        let _ = song_request_stream_sender.send(SongRequest {
+                            id,
+                            song: song.to_string(),
+                            from_library: relay_message["from_library"]
+                                .as_bool()
+                                .unwrap_or_default(),
+                        });
+                    }
+                }
+                Some("call_waiting") => {
+                    if let (Some(id), Some(name)) =
+                        (relay_message["id"].as_u64(), relay_message["name"].as_str())
+                    {
+                        let _ = call_notice_sender.send(CallNotice::Waiting {
+                            id,
+                            name: name.to_string(),
+                        });
+                    }
+                }
+                Some("call_ended") => {
+                    if let Some(id) = relay_message["id"].as_u64() {
+                        let _ = call_notice_sender.send(CallNotice::Ended { id });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn decode_call_frame(call_frame: &[u8]) -> Vec<f32> {
+    let mut uncompressed_data = String::new();
+    if let Err(err_val) = Decompressor::new(call_frame, 4096).read_to_string(&mut uncompressed_data)
+    {
+        eprintln!("Error: Call Frame | {}", err_val);
+        return vec![];
+    }
+    let mut call_data = vec![];
+    let mut call_datum = String::new();
+    for char in uncompressed_data.chars() {
+        if char == '+' || char == '-' {
+            if !call_datum.is_empty() {
+                call_data.push(call_datum.parse().unwrap_or(0.0));
+                call_datum.clear();
+            }
+            call_datum.push(char);
+            call_datum.push_str("0.");
+        } else {
+            call_datum.push(char);
+        }
+    }
+    if !call_datum.is_empty() {
+        call_data.push(call_datum.parse().unwrap_or(0.0));
+    }
+    call_data
+}
+
 async fn status_checker(
     message_organizer_task: JoinHandle<()>,
     stream_task: JoinHandle<()>,
     mixer_task: JoinHandle<()>,
+    relay_message_task: JoinHandle<()>,
     mut base_to_streaming: Receiver<bool>,
     streaming_to_base: Sender<bool>,
     streaming_to_base_sender_is_finished: Sender<bool>,
@@ -303,6 +560,7 @@ async fn status_checker(
     }
     stream_task.abort();
     mixer_task.abort();
+    relay_message_task.abort();
     message_organizer_task.abort();
     if problem {
         match streaming_to_base_sender_is_finished.send(true) {
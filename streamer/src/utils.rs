@@ -19,5 +19,9 @@ pub fn get_config() -> Config {
         quality: configs_cleaned[1].parse().unwrap(),
         latency: configs_cleaned[2].parse().unwrap(),
         tls: configs_cleaned[3].parse().unwrap(),
+        token: match configs_cleaned[4] {
+            "none" => None,
+            token => Some(token.to_string()),
+        },
     }
 }